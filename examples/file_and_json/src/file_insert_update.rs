@@ -2,7 +2,10 @@
 #![allow(dead_code)]
 
 use aws_config::{meta::region::RegionProviderChain, Region};
-use s3_bucket::{traits::has_key::HasKey, S3BucketItem, S3Context};
+use s3_bucket::{
+    traits::{has_key::HasKey, key_parser::KeyParser},
+    S3BucketItem, S3Context,
+};
 use strum::{Display, EnumString};
 
 pub async fn test() {    
@@ -26,6 +29,7 @@ pub async fn test() {
 #[s3_item_prop(bucket = get_bucket_name())]
 #[s3_item_prop(key = "my-app-builds/{target_os}/{target_arch}/{version}/{name}")]
 #[s3_item_prop(content_type = "application/octet-stream")]
+#[s3_item_prop(parse)]
 pub struct MyAppBuild {
     pub target_os: TargetOs,
     pub target_arch: TargetArch,
@@ -62,7 +66,7 @@ impl TryInto<s3_bucket::bytes::Bytes> for MyAppBuild {
         let content = match self.file {
             File::FilePath(x) => {
                 let file_content =
-                    std::fs::read(x).map_err(|_| s3_bucket::error::Error::TryIntoByteError)?;
+                    std::fs::read(x).map_err(s3_bucket::error::Error::IoError)?;
                 s3_bucket::bytes::Bytes::from_owner(file_content)
             }
             File::FileContent(bytes) => bytes,
@@ -77,30 +81,12 @@ impl TryInto<s3_bucket::bytes::Bytes> for MyAppBuild {
 impl TryFrom<s3_bucket::s3_object::S3Object> for MyAppBuild {
     type Error = s3_bucket::error::Error;
     fn try_from(value: s3_bucket::s3_object::S3Object) -> Result<Self, Self::Error> {
-        let mut parts = value.key.split('/');
-
-        let _prefix = parts.next();
-        let target_os = parts
-            .next()
-            .ok_or(s3_bucket::error::Error::Other("Parse error.".into()))?
-            .parse::<TargetOs>()
-            .map_err(|_| s3_bucket::error::Error::Other("Parse error.".into()))?;
-
-        let target_arch = parts
-            .next()
-            .ok_or(s3_bucket::error::Error::Other("Parse error.".into()))?
-            .parse::<TargetArch>()
-            .map_err(|_| s3_bucket::error::Error::Other("Parse error.".into()))?;
-
-        let version = parts
-            .next()
-            .ok_or(s3_bucket::error::Error::Other("Parse error.".into()))?
-            .to_string();
-
-        let name = parts
-            .next()
-            .ok_or(s3_bucket::error::Error::Other("Parse error.".into()))?
-            .to_string();
+        let MyAppBuildKey {
+            target_os,
+            target_arch,
+            version,
+            name,
+        } = MyAppBuild::parse_key(&value.key)?;
 
         Ok(MyAppBuild {
             target_os,
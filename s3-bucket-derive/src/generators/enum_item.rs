@@ -0,0 +1,349 @@
+use proc_macro2::TokenStream;
+use quote::{ToTokens, quote};
+
+use crate::{
+    generators::key_rendering::{
+        generate_key_arguments, generate_render_key, generate_segment_parser,
+    },
+    struct_info::{StructInfo, VariantInfo},
+    utils::as_expr::AsExpr,
+};
+
+/// Content type used by enum items with per variant content types and no default.
+const FALLBACK_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Generates the key and content type implementations of an enum item, dispatching on the variant.
+pub fn generate_enum_item(struct_info: &StructInfo) -> TokenStream {
+    let Some(variants) = &struct_info.variants else {
+        return quote! {};
+    };
+
+    let content_type = generate_content_type(struct_info, variants);
+    if variants.iter().any(|x| x.key.is_none()) {
+        return content_type;
+    }
+
+    let has_key = generate_has_key(struct_info, variants);
+    let typed_key = generate_typed_key(struct_info, variants);
+    let key_parser = if struct_info.parse.is_some() {
+        generate_key_parser(struct_info, variants)
+    } else {
+        quote! {}
+    };
+    quote! {
+        #content_type
+        #has_key
+        #typed_key
+        #key_parser
+    }
+}
+
+fn generate_content_type(struct_info: &StructInfo, variants: &[VariantInfo]) -> TokenStream {
+    let has_variant_content_types = variants.iter().any(|x| x.content_type.is_some());
+    if struct_info.content_type.is_none() && !has_variant_content_types {
+        return quote! {};
+    }
+
+    let default_content_type = struct_info
+        .content_type
+        .as_deref()
+        .unwrap_or(FALLBACK_CONTENT_TYPE);
+    let mut arms = quote! {};
+    for variant in variants {
+        let variant_expr = variant.name.as_expr();
+        let content_type = variant
+            .content_type
+            .as_deref()
+            .unwrap_or(default_content_type);
+        quote! {
+            Self::#variant_expr { .. } => String::from(#content_type),
+        }
+        .to_tokens(&mut arms);
+    }
+
+    let struct_name_expr = struct_info.struct_name.as_expr();
+    let generics = struct_info.impl_generics();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics s3_bucket::traits::has_content_type::HasContentType for #struct_name_expr #ty_generics #where_clause {
+            fn get_content_type() -> String {
+                String::from(#default_content_type)
+            }
+
+            fn content_type(&self) -> String {
+                match self {
+                    #arms
+                }
+            }
+        }
+    }
+}
+
+/// Match arms building the key of each variant, from bindings named after the key fields. With
+/// `checked` the arms return a `Result` like `HasKey::try_get_key`.
+fn get_key_arms(variants: &[VariantInfo], checked: bool) -> TokenStream {
+    let mut arms = quote! {};
+    for variant in variants {
+        let Some(key) = &variant.key else {
+            continue;
+        };
+        let variant_expr = variant.name.as_expr();
+        let key_value = &key.value;
+        if key.is_static_key() {
+            let key = quote! { String::from(#key_value) };
+            let key = if checked {
+                quote! { Ok(#key) }
+            } else {
+                key
+            };
+            quote! {
+                Self::#variant_expr { .. } => #key,
+            }
+            .to_tokens(&mut arms);
+            continue;
+        }
+
+        let bindings = key.unique_arguments().into_iter().map(|x| x.as_expr());
+        let arguments = generate_key_arguments(key, |field| {
+            let field_expr = field.to_string().as_expr();
+            quote! { (*#field_expr) }
+        });
+        let render_key = generate_render_key(key, &variant.fields, checked);
+        quote! {
+            Self::#variant_expr { #(#bindings,)* .. } => {
+                #arguments
+                #render_key
+            }
+        }
+        .to_tokens(&mut arms);
+    }
+    arms
+}
+
+fn generate_has_key(struct_info: &StructInfo, variants: &[VariantInfo]) -> TokenStream {
+    let struct_name_expr = struct_info.struct_name.as_expr();
+    let arms = get_key_arms(variants, false);
+    let try_arms = get_key_arms(variants, true);
+    let generics = struct_info.impl_generics();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics s3_bucket::traits::has_key::HasKey for #struct_name_expr #ty_generics #where_clause {
+            fn get_key(&self) -> String {
+                match self {
+                    #arms
+                }
+            }
+
+            fn try_get_key(&self) -> Result<String, s3_bucket::error::KeyBuildError> {
+                match self {
+                    #try_arms
+                }
+            }
+        }
+    }
+}
+
+fn generate_typed_key(struct_info: &StructInfo, variants: &[VariantInfo]) -> TokenStream {
+    let struct_name_expr = struct_info.struct_name.as_expr();
+    let visibility = &struct_info.visibility;
+    let typed_key_name = syn::Ident::new(
+        &struct_info.typed_key_name(),
+        proc_macro2::Span::call_site(),
+    );
+    let typed_key_name_string = struct_info.typed_key_name();
+    let doc = format!("Typed key of [`{}`].", struct_info.struct_name);
+
+    let mut key_variants = quote! {};
+    let mut from_item = quote! {};
+    for variant in variants {
+        let Some(key) = &variant.key else {
+            continue;
+        };
+        let variant_expr = variant.name.as_expr();
+        if key.is_static_key() {
+            quote! { #variant_expr, }.to_tokens(&mut key_variants);
+            quote! {
+                #struct_name_expr::#variant_expr { .. } => Self::#variant_expr,
+            }
+            .to_tokens(&mut from_item);
+            continue;
+        }
+
+        let mut fields = quote! {};
+        let mut values = quote! {};
+        for argument in key.unique_arguments() {
+            let Some(field) = variant.fields.iter().find(|x| &x.name == argument) else {
+                continue;
+            };
+            let field_type = &field.ty;
+            let argument_expr = argument.as_expr();
+            quote! { #argument_expr: #field_type, }.to_tokens(&mut fields);
+            quote! { #argument_expr: #argument_expr.clone(), }.to_tokens(&mut values);
+        }
+        let bindings = key.unique_arguments().into_iter().map(|x| x.as_expr());
+        quote! { #variant_expr { #fields }, }.to_tokens(&mut key_variants);
+        quote! {
+            #struct_name_expr::#variant_expr { #(#bindings,)* .. } => Self::#variant_expr { #values },
+        }
+        .to_tokens(&mut from_item);
+    }
+    let arms = get_key_arms(variants, false);
+    let try_arms = get_key_arms(variants, true);
+
+    let generics = struct_info.impl_generics();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let key_generics = struct_info.typed_key_generics();
+    let (key_impl_generics, key_ty_generics, key_where_clause) = key_generics.split_for_impl();
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Clone)]
+        #visibility enum #typed_key_name #key_generics #key_where_clause {
+            #key_variants
+        }
+
+        impl #key_impl_generics s3_bucket::traits::has_key::HasKey for #typed_key_name #key_ty_generics #key_where_clause {
+            fn get_key(&self) -> String {
+                match self {
+                    #arms
+                }
+            }
+
+            fn try_get_key(&self) -> Result<String, s3_bucket::error::KeyBuildError> {
+                match self {
+                    #try_arms
+                }
+            }
+        }
+
+        impl #key_impl_generics std::fmt::Debug for #typed_key_name #key_ty_generics #key_where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                use s3_bucket::traits::has_key::HasKey;
+                let mut tuple = f.debug_tuple(#typed_key_name_string);
+                match self.try_get_key() {
+                    Ok(key) => tuple.field(&key),
+                    Err(e) => tuple.field(&e),
+                }
+                .finish()
+            }
+        }
+
+        impl #impl_generics From<&#struct_name_expr #ty_generics> for #typed_key_name #key_ty_generics #where_clause {
+            fn from(item: &#struct_name_expr #ty_generics) -> Self {
+                match item {
+                    #from_item
+                }
+            }
+        }
+
+        impl #impl_generics s3_bucket::traits::has_typed_key::HasTypedKey for #struct_name_expr #ty_generics #where_clause {
+            type TypedKey = #typed_key_name #key_ty_generics;
+        }
+    }
+}
+
+/// Tries the key template of every variant in declaration order, the first one matching the shape
+/// of the key and parsing its segments wins.
+fn generate_key_parser(struct_info: &StructInfo, variants: &[VariantInfo]) -> TokenStream {
+    let struct_name_expr = struct_info.struct_name.as_expr();
+    let typed_key_name_expr = struct_info.typed_key_name().as_expr();
+
+    let mut attempts = quote! {};
+    let mut templates = vec![];
+    let mut has_arguments = false;
+    for variant in variants {
+        let Some(key) = &variant.key else {
+            continue;
+        };
+        let variant_expr = variant.name.as_expr();
+        let key_value = &key.value;
+        templates.push(key_value.clone());
+        if key.is_static_key() {
+            quote! {
+                if s3_bucket::traits::key_parser::match_key_template(#key_value, key).is_ok() {
+                    return Ok(#typed_key_name_expr::#variant_expr);
+                }
+            }
+            .to_tokens(&mut attempts);
+            continue;
+        }
+
+        has_arguments = true;
+        let mut values = quote! {};
+        let mut fields = quote! {};
+        for argument in key.unique_arguments() {
+            let Some(field) = variant.fields.iter().find(|x| &x.name == argument) else {
+                continue;
+            };
+            let field_type = &field.ty;
+            let segment_parser = generate_segment_parser(field);
+            let argument_expr = argument.as_expr();
+            let index = key.arguments.iter().position(|x| x == argument).unwrap();
+            quote! {
+                let #argument_expr = match #segment_parser::<#field_type>(
+                    key,
+                    #argument,
+                    segments[#index],
+                ) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        error.get_or_insert(e);
+                        break 'variant;
+                    }
+                };
+            }
+            .to_tokens(&mut values);
+            quote! { #argument_expr, }.to_tokens(&mut fields);
+        }
+        quote! {
+            'variant: {
+                let Ok(segments) = s3_bucket::traits::key_parser::match_key_template(#key_value, key) else {
+                    break 'variant;
+                };
+                #values
+                return Ok(#typed_key_name_expr::#variant_expr { #fields });
+            }
+        }
+        .to_tokens(&mut attempts);
+    }
+
+    // A key matching the shape of a variant but failing to parse reports the parse error.
+    let (error_declaration, no_match) = if has_arguments {
+        (
+            quote! { let mut error = None; },
+            quote! {
+                if let Some(error) = error {
+                    return Err(error);
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+    let template = templates.join(" | ");
+    let reason = format!(
+        "the key matches none of the variants of `{}`",
+        struct_info.struct_name
+    );
+
+    let generics = struct_info.impl_generics();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let key_generics = struct_info.typed_key_generics();
+    let (_, key_ty_generics, _) = key_generics.split_for_impl();
+    quote! {
+        impl #impl_generics s3_bucket::traits::key_parser::KeyParser for #struct_name_expr #ty_generics #where_clause {
+            type KeyParts = #typed_key_name_expr #key_ty_generics;
+
+            fn parse_key(key: &str) -> Result<Self::KeyParts, s3_bucket::error::Error> {
+                #error_declaration
+                #attempts
+                #no_match
+                Err(s3_bucket::error::Error::KeyMismatch {
+                    key: key.to_string(),
+                    template: String::from(#template),
+                    reason: String::from(#reason),
+                })
+            }
+        }
+    }
+}
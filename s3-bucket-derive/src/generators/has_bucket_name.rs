@@ -8,12 +8,13 @@ pub fn generate_has_bucket_name_tokens(struct_info: &StructInfo) -> proc_macro2:
     };
 
     let struct_name_expr = struct_info.struct_name.as_expr();
-    let bucket_name_expr = bucket_name.as_expr();
+    let generics = struct_info.impl_generics();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     quote! {
-        impl s3_bucket::traits::has_bucket_name::HasBucketName for #struct_name_expr {
+        impl #impl_generics s3_bucket::traits::has_bucket_name::HasBucketName for #struct_name_expr #ty_generics #where_clause {
             fn get_bucket_name() -> String {
-                #bucket_name_expr.clone()
+                #bucket_name.clone()
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -7,11 +7,13 @@ pub fn generate_has_content_type(struct_info: &StructInfo) -> proc_macro2::Token
         return quote! {};
     };
     let struct_name_expr = struct_info.struct_name.as_expr();
+    let generics = struct_info.impl_generics();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     quote! {
-        impl s3_bucket::traits::has_content_type::HasContentType for #struct_name_expr {
+        impl #impl_generics s3_bucket::traits::has_content_type::HasContentType for #struct_name_expr #ty_generics #where_clause {
             fn get_content_type() -> String {
                 String::from(#content_type)
             }
         }
     }
-}
\ No newline at end of file
+}
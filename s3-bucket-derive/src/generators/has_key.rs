@@ -1,6 +1,9 @@
-use quote::{ToTokens, quote};
+use quote::quote;
 
-use crate::{struct_info::StructInfo, utils::as_expr::AsExpr};
+use crate::{
+    generators::key_rendering::generate_key_arguments, struct_info::StructInfo,
+    utils::as_expr::AsExpr,
+};
 
 pub fn generate_has_key_token(struct_info: &StructInfo) -> proc_macro2::TokenStream {
     let struct_name_expr = struct_info.struct_name.as_expr();
@@ -9,33 +12,35 @@ pub fn generate_has_key_token(struct_info: &StructInfo) -> proc_macro2::TokenStr
         return quote! {};
     };
     let key_value = &key.value;
-    let key_token = if key.is_static_key() {
-        quote! {String::from(#key_value)}
-    } else {
-        let mut build_key_expr = quote! {
-            let mut arguments: Vec<Box<dyn std::fmt::Display + Send>> = vec![];
-        };
-
-        for argument in &key.arguments {
-            let argument_expr = argument.as_expr();
-            quote! {
-                arguments.push(Box::new(self.#argument_expr.clone()));
+    let key_tokens = if key.is_static_key() {
+        quote! {
+            fn get_key(&self) -> String {
+                String::from(#key_value)
             }
-            .to_tokens(&mut build_key_expr);
         }
+    } else {
+        let arguments = generate_key_arguments(key, |field| {
+            let field_expr = field.to_string().as_expr();
+            quote! { self.#field_expr }
+        });
         quote! {
-            #struct_name_expr::build_key(arguments)
+            fn get_key(&self) -> String {
+                #arguments
+                <Self as s3_bucket::traits::key_builder::KeyBuilder>::build_key(arguments)
+            }
+
+            fn try_get_key(&self) -> Result<String, s3_bucket::error::KeyBuildError> {
+                #arguments
+                <Self as s3_bucket::traits::key_builder::KeyBuilder>::try_build_key(arguments)
+            }
         }
-        .to_tokens(&mut build_key_expr);
-        build_key_expr
     };
 
+    let generics = struct_info.impl_generics();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     quote! {
-        impl s3_bucket::traits::has_key::HasKey for #struct_name_expr {
-            fn get_key(&self) -> String {
-                use s3_bucket::traits::key_builder::KeyBuilder;
-                #key_token
-            }
+        impl #impl_generics s3_bucket::traits::has_key::HasKey for #struct_name_expr #ty_generics #where_clause {
+            #key_tokens
         }
     }
 }
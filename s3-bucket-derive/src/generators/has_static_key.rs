@@ -14,8 +14,10 @@ pub fn generate_has_static_key(struct_info: &StructInfo) -> proc_macro2::TokenSt
     }
 
     let key_value = &key.value;
+    let generics = struct_info.impl_generics();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     quote! {
-        impl s3_bucket::traits::has_static_key::HasStaticKey for #struct_name_expr {
+        impl #impl_generics s3_bucket::traits::has_static_key::HasStaticKey for #struct_name_expr #ty_generics #where_clause {
             fn get_static_key() -> String {
                 String::from(#key_value)
             }
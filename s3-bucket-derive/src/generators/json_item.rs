@@ -1,4 +1,5 @@
 use quote::quote;
+use syn::parse_quote;
 
 use crate::{struct_info::StructInfo, utils::as_expr::AsExpr};
 
@@ -6,25 +7,36 @@ pub fn generate_byte_stream_conversion_for_json_item(
     struct_info: &StructInfo,
 ) -> proc_macro2::TokenStream {
     let struct_name_expr = struct_info.struct_name.as_expr();
+
+    let mut serialize_generics = struct_info.impl_generics();
+    serialize_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(Self: s3_bucket::serde::Serialize));
+    let (impl_generics, ty_generics, serialize_where_clause) = serialize_generics.split_for_impl();
+
+    let mut deserialize_generics = struct_info.impl_generics();
+    deserialize_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(Self: s3_bucket::serde::de::DeserializeOwned));
+    let (_, _, deserialize_where_clause) = deserialize_generics.split_for_impl();
+
     quote! {
-        impl TryInto<s3_bucket::bytes::Bytes> for #struct_name_expr {
-            type Error = s3_bucket::error::Error;
+        impl #impl_generics TryInto<s3_bucket::bytes::Bytes> for #struct_name_expr #ty_generics #serialize_where_clause {
+            type Error = s3_bucket::serde_json::Error;
 
             fn try_into(self) -> Result<s3_bucket::bytes::Bytes, Self::Error> {
-                let json = s3_bucket::serde_json::to_string(&self)
-                    .map_err(|_| s3_bucket::error::Error::TryIntoByteError)?;
+                let json = s3_bucket::serde_json::to_vec(&self)?;
                 Ok(s3_bucket::bytes::Bytes::from_owner(json))
             }
         }
 
-        impl TryFrom<s3_bucket::s3_object::S3Object> for #struct_name_expr {
-            type Error = s3_bucket::error::Error;
+        impl #impl_generics TryFrom<s3_bucket::s3_object::S3Object> for #struct_name_expr #ty_generics #deserialize_where_clause {
+            type Error = s3_bucket::serde_json::Error;
 
             fn try_from(value: s3_bucket::s3_object::S3Object) -> Result<Self, Self::Error> {
-                let string = str::from_utf8(&value.bytes)
-                    .map_err(|_|  s3_bucket::error::Error::ByteArrayToString)?;
-                s3_bucket::serde_json::from_str(string)
-                    .map_err(|_| s3_bucket::error::Error::TryFromByteError)
+                s3_bucket::serde_json::from_slice(&value.bytes)
             }
         }
     }
@@ -1,6 +1,8 @@
 use quote::quote;
 
-use crate::{struct_info::StructInfo, utils::as_expr::AsExpr};
+use crate::{
+    generators::key_rendering::generate_render_key, struct_info::StructInfo, utils::as_expr::AsExpr,
+};
 
 pub fn generate_key_buidler(struct_info: &StructInfo) -> proc_macro2::TokenStream {
     let struct_name_expr = struct_info.struct_name.as_expr();
@@ -12,18 +14,20 @@ pub fn generate_key_buidler(struct_info: &StructInfo) -> proc_macro2::TokenStrea
         return quote! {};
     }
 
-    let mut key_string = key.value.clone();
-    for argument in &key.arguments {
-        key_string = key_string.replace(&format!("{{{}}}", argument), "{}");
-    }
-
+    let render_key = generate_render_key(key, &struct_info.fields, false);
+    let try_render_key = generate_render_key(key, &struct_info.fields, true);
+    let generics = struct_info.impl_generics();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     quote! {
-        impl s3_bucket::traits::key_builder::KeyBuilder for #struct_name_expr {
-            fn build_key(arguments: Vec<Box<dyn std::fmt::Display + Send>>) -> String {
-                let mut key_string = String::from(#key_string);
-                arguments
-                    .iter()
-                    .fold(key_string, |acc, v| acc.replacen("{}", &v.to_string(), 1))
+        impl #impl_generics s3_bucket::traits::key_builder::KeyBuilder for #struct_name_expr #ty_generics #where_clause {
+            fn build_key(arguments: Vec<Box<dyn std::fmt::Display + Send + '_>>) -> String {
+                #render_key
+            }
+
+            fn try_build_key(
+                arguments: Vec<Box<dyn std::fmt::Display + Send + '_>>,
+            ) -> Result<String, s3_bucket::error::KeyBuildError> {
+                #try_render_key
             }
         }
     }
@@ -0,0 +1,70 @@
+use quote::{ToTokens, quote};
+
+use crate::{
+    generators::key_rendering::generate_segment_parser, struct_info::StructInfo,
+    utils::as_expr::AsExpr,
+};
+
+pub fn generate_key_parser(struct_info: &StructInfo) -> proc_macro2::TokenStream {
+    let struct_name_expr = struct_info.struct_name.as_expr();
+    let Some(key) = &struct_info.key else {
+        return quote! {};
+    };
+
+    // Opt-in, as the parser needs `FromStr` on every key field.
+    if struct_info.parse.is_none() {
+        return quote! {};
+    }
+
+    let key_value = &key.value;
+    let generics = struct_info.impl_generics();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    if key.is_static_key() {
+        return quote! {
+            impl #impl_generics s3_bucket::traits::key_parser::KeyParser for #struct_name_expr #ty_generics #where_clause {
+                type KeyParts = ();
+
+                fn parse_key(key: &str) -> Result<Self::KeyParts, s3_bucket::error::Error> {
+                    s3_bucket::traits::key_parser::match_key_template(#key_value, key)?;
+                    Ok(())
+                }
+            }
+        };
+    }
+
+    let typed_key_name_expr = struct_info.typed_key_name().as_expr();
+    let key_generics = struct_info.typed_key_generics();
+    let (_, key_ty_generics, _) = key_generics.split_for_impl();
+    let mut values = quote! {};
+    for argument in key.unique_arguments() {
+        let Some(field) = struct_info.get_field(argument) else {
+            continue;
+        };
+        let field_type = &field.ty;
+        let segment_parser = generate_segment_parser(field);
+        let argument_expr = argument.as_expr();
+        let index = key.arguments.iter().position(|x| x == argument).unwrap();
+
+        quote! {
+            #argument_expr: #segment_parser::<#field_type>(
+                key,
+                #argument,
+                segments[#index],
+            )?,
+        }
+        .to_tokens(&mut values);
+    }
+
+    quote! {
+        impl #impl_generics s3_bucket::traits::key_parser::KeyParser for #struct_name_expr #ty_generics #where_clause {
+            type KeyParts = #typed_key_name_expr #key_ty_generics;
+
+            fn parse_key(key: &str) -> Result<Self::KeyParts, s3_bucket::error::Error> {
+                let segments = s3_bucket::traits::key_parser::match_key_template(#key_value, key)?;
+                Ok(#typed_key_name_expr {
+                    #values
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::{ToTokens, format_ident, quote};
+
+use crate::struct_info::{
+    FieldInfo, Key,
+    placeholder::{Placeholder, SegmentFormat, SegmentPolicy, TransformKind},
+};
+
+/// Declares `arguments` with the value of every placeholder of `key`, `value` giving the
+/// expression of a field. Dates are turned into their `KeyDateParts` here, where the type of the
+/// field is known; the key builder only sees `Display` values and formats them like dates given
+/// as partial keys.
+pub fn generate_key_arguments(key: &Key, value: impl Fn(&str) -> TokenStream) -> TokenStream {
+    let mut arguments = quote! {
+        let mut arguments: Vec<Box<dyn std::fmt::Display + Send + '_>> = vec![];
+    };
+    for placeholder in &key.placeholders {
+        let value = value(&placeholder.field);
+        match &placeholder.format {
+            Some(SegmentFormat::Date(_)) => quote! {
+                arguments.push(Box::new(s3_bucket::key_format::KeyDate::key_date_parts(&#value)));
+            },
+            _ => quote! {
+                arguments.push(Box::new(#value.clone()));
+            },
+        }
+        .to_tokens(&mut arguments);
+    }
+    arguments
+}
+
+/// Path of the function parsing a key segment back into `field`.
+pub fn generate_segment_parser(field: &FieldInfo) -> TokenStream {
+    if field.segment_policy == Some(SegmentPolicy::PercentEncode) {
+        quote! { s3_bucket::traits::key_parser::parse_encoded_key_segment }
+    } else {
+        quote! { s3_bucket::traits::key_parser::parse_key_segment }
+    }
+}
+
+/// Builds the key from the `arguments` in scope, applying the formats, transforms and segment
+/// policies of the placeholders. With `checked` the expression is a
+/// `Result<String, KeyBuildError>` failing on rejected values.
+pub fn generate_render_key(key: &Key, fields: &[FieldInfo], checked: bool) -> TokenStream {
+    let key_string = key.template();
+    let mut segments = quote! {};
+    for placeholder in &key.placeholders {
+        let field = &placeholder.field;
+        let field_policy = fields
+            .iter()
+            .find(|x| &x.name == field)
+            .and_then(|x| x.segment_policy);
+        let policy = format_ident!("{}", placeholder.policy(field_policy).variant_name());
+        quote! {
+            s3_bucket::traits::key_builder::KeySegment {
+                field: #field,
+                policy: s3_bucket::traits::key_builder::SegmentPolicy::#policy,
+            },
+        }
+        .to_tokens(&mut segments);
+    }
+    let fill = if checked {
+        quote! { s3_bucket::traits::key_builder::try_fill_key_template }
+    } else {
+        quote! { s3_bucket::traits::key_builder::fill_key_template }
+    };
+    let fill = quote! {
+        #fill(#key_string, &[#segments], arguments)
+    };
+    if key.is_plain() {
+        return fill;
+    }
+
+    let mut arms = quote! {};
+    for (index, placeholder) in key.placeholders.iter().enumerate() {
+        if placeholder.is_plain() {
+            continue;
+        }
+
+        let index = Literal::usize_unsuffixed(index);
+        let render = generate_render_placeholder(placeholder);
+        quote! {
+            #index => {
+                #render
+                Box::new(value)
+            }
+        }
+        .to_tokens(&mut arms);
+    }
+
+    quote! {
+        let arguments: Vec<Box<dyn std::fmt::Display + Send + '_>> = arguments
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| -> Box<dyn std::fmt::Display + Send + '_> {
+                match index {
+                    #arms
+                    _ => value,
+                }
+            })
+            .collect();
+        #fill
+    }
+}
+
+/// Turns the `value` in scope into a `String` with the format and transforms of `placeholder`.
+fn generate_render_placeholder(placeholder: &Placeholder) -> TokenStream {
+    let mut render = match &placeholder.format {
+        Some(SegmentFormat::Std(format)) => {
+            let format = format!("{{:{format}}}");
+            quote! { let value = format!(#format, value); }
+        }
+        Some(SegmentFormat::Date(format)) => quote! {
+            let value = s3_bucket::key_format::format_key_date_str(&value.to_string(), #format);
+        },
+        None => quote! { let value = value.to_string(); },
+    };
+    for transform in &placeholder.transforms {
+        match transform.kind {
+            TransformKind::Lower => quote! { let value = value.to_lowercase(); },
+            TransformKind::Upper => quote! { let value = value.to_uppercase(); },
+            TransformKind::Slug => quote! { let value = s3_bucket::key_format::slug(&value); },
+            TransformKind::Sha256 => {
+                quote! { let value = s3_bucket::key_format::sha256(&value); }
+            }
+        }
+        .to_tokens(&mut render);
+        if let Some((start, end)) = transform.slice {
+            let start = Literal::usize_unsuffixed(start);
+            let end = match end {
+                Some(x) => {
+                    let x = Literal::usize_unsuffixed(x);
+                    quote! { Some(#x) }
+                }
+                None => quote! { None },
+            };
+            quote! { let value = s3_bucket::key_format::slice(&value, #start, #end); }
+                .to_tokens(&mut render);
+        }
+    }
+    render
+}
@@ -1,6 +1,10 @@
+pub mod enum_item;
 pub mod has_bucket_name;
 pub mod has_content_type;
 pub mod has_key;
 pub mod has_static_key;
 pub mod json_item;
 pub mod key_builder;
+pub mod key_parser;
+pub mod key_rendering;
+pub mod typed_key;
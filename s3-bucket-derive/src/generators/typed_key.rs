@@ -0,0 +1,103 @@
+use quote::{ToTokens, quote};
+
+use crate::{
+    generators::key_rendering::{generate_key_arguments, generate_render_key},
+    struct_info::StructInfo,
+    utils::as_expr::AsExpr,
+};
+
+pub fn generate_typed_key(struct_info: &StructInfo) -> proc_macro2::TokenStream {
+    let struct_name_expr = struct_info.struct_name.as_expr();
+    let Some(key) = &struct_info.key else {
+        return quote! {};
+    };
+
+    let visibility = &struct_info.visibility;
+    let typed_key_name = syn::Ident::new(
+        &struct_info.typed_key_name(),
+        proc_macro2::Span::call_site(),
+    );
+    let typed_key_name_string = struct_info.typed_key_name();
+    let doc = format!("Typed key of [`{}`].", struct_info.struct_name);
+
+    let mut fields = quote! {};
+    let mut from_item = quote! {};
+    for argument in key.unique_arguments() {
+        let Some(field) = struct_info.get_field(argument) else {
+            continue;
+        };
+        let field_type = &field.ty;
+        let argument_expr = argument.as_expr();
+        quote! {
+            #visibility #argument_expr: #field_type,
+        }
+        .to_tokens(&mut fields);
+        quote! {
+            #argument_expr: item.#argument_expr.clone(),
+        }
+        .to_tokens(&mut from_item);
+    }
+
+    let arguments = generate_key_arguments(key, |field| {
+        let field_expr = field.to_string().as_expr();
+        quote! { self.#field_expr }
+    });
+    let render_key = generate_render_key(key, &struct_info.fields, false);
+    let try_render_key = generate_render_key(key, &struct_info.fields, true);
+    let generics = struct_info.impl_generics();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let key_generics = struct_info.typed_key_generics();
+    let (key_impl_generics, key_ty_generics, key_where_clause) = key_generics.split_for_impl();
+
+    // Static keys get a unit typed key, like unit variants of enum items.
+    let (definition, item, from_item) = match key.is_static_key() {
+        true => (quote! { ; }, quote! { _ }, quote! { Self }),
+        false => (
+            quote! { { #fields } },
+            quote! { item },
+            quote! { Self { #from_item } },
+        ),
+    };
+
+    // The key can't go through the item's KeyBuilder, which may need type parameters the typed key
+    // doesn't have.
+    quote! {
+        #[doc = #doc]
+        #[derive(Clone)]
+        #visibility struct #typed_key_name #key_generics #key_where_clause #definition
+
+        impl #key_impl_generics s3_bucket::traits::has_key::HasKey for #typed_key_name #key_ty_generics #key_where_clause {
+            fn get_key(&self) -> String {
+                #arguments
+                #render_key
+            }
+
+            fn try_get_key(&self) -> Result<String, s3_bucket::error::KeyBuildError> {
+                #arguments
+                #try_render_key
+            }
+        }
+
+        impl #key_impl_generics std::fmt::Debug for #typed_key_name #key_ty_generics #key_where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                use s3_bucket::traits::has_key::HasKey;
+                let mut tuple = f.debug_tuple(#typed_key_name_string);
+                match self.try_get_key() {
+                    Ok(key) => tuple.field(&key),
+                    Err(e) => tuple.field(&e),
+                }
+                .finish()
+            }
+        }
+
+        impl #impl_generics From<&#struct_name_expr #ty_generics> for #typed_key_name #key_ty_generics #where_clause {
+            fn from(#item: &#struct_name_expr #ty_generics) -> Self {
+                #from_item
+            }
+        }
+
+        impl #impl_generics s3_bucket::traits::has_typed_key::HasTypedKey for #struct_name_expr #ty_generics #where_clause {
+            type TypedKey = #typed_key_name #key_ty_generics;
+        }
+    }
+}
@@ -1,13 +1,14 @@
 #![deny(unused_crate_dependencies)]
 
 use generators::{
-    has_bucket_name::generate_has_bucket_name_tokens, has_content_type::generate_has_content_type,
-    has_key::generate_has_key_token, json_item::generate_byte_stream_conversion_for_json_item,
-    key_builder::generate_key_buidler,
+    enum_item::generate_enum_item, has_bucket_name::generate_has_bucket_name_tokens,
+    has_content_type::generate_has_content_type, has_key::generate_has_key_token,
+    json_item::generate_byte_stream_conversion_for_json_item, key_builder::generate_key_buidler,
+    key_parser::generate_key_parser, typed_key::generate_typed_key,
 };
 use proc_macro::TokenStream;
 use quote::quote;
-use struct_info::StructInfo;
+use struct_info::{StructInfo, parse_bounds};
 use syn::{DeriveInput, parse_macro_input};
 
 use crate::{generators::has_static_key, has_static_key::generate_has_static_key};
@@ -19,13 +20,27 @@ mod utils;
 #[proc_macro_derive(S3BucketItem, attributes(s3_item_prop))]
 pub fn s3_bucket_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let struct_info = StructInfo::from(input);
+    let struct_info = match StructInfo::try_from(input) {
+        Ok(x) => x,
+        Err(e) => return e.to_compile_error().into(),
+    };
 
     let has_bucket_name_token = generate_has_bucket_name_tokens(&struct_info);
+    if struct_info.variants.is_some() {
+        let enum_item_token = generate_enum_item(&struct_info);
+        return quote! {
+            #has_bucket_name_token
+            #enum_item_token
+        }
+        .into();
+    }
+
     let has_content_type_token = generate_has_content_type(&struct_info);
     let has_key_token = generate_has_key_token(&struct_info);
     let key_builder_token = generate_key_buidler(&struct_info);
     let has_static_key = generate_has_static_key(&struct_info);
+    let key_parser_token = generate_key_parser(&struct_info);
+    let typed_key_token = generate_typed_key(&struct_info);
 
     quote! {
         #has_bucket_name_token
@@ -33,14 +48,21 @@ pub fn s3_bucket_derive(input: TokenStream) -> TokenStream {
         #has_key_token
         #key_builder_token
         #has_static_key
+        #key_parser_token
+        #typed_key_token
     }
     .into()
 }
 
-#[proc_macro_derive(JsonItem)]
+#[proc_macro_derive(JsonItem, attributes(s3_item_prop))]
 pub fn json_item_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let struct_info = StructInfo::from(input);
+    // The s3_item_prop attributes are validated by S3BucketItem, only the bounds are needed here.
+    let mut struct_info = StructInfo::new(input.ident.to_string(), input.vis, input.generics);
+    struct_info.bounds = match parse_bounds(&input.attrs) {
+        Ok(x) => x,
+        Err(e) => return e.to_compile_error().into(),
+    };
     let conversion = generate_byte_stream_conversion_for_json_item(&struct_info);
     quote! {
         #conversion
@@ -1,156 +1,632 @@
+use std::collections::HashSet;
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::ToTokens;
 use regex::Regex;
-use syn::{Data, DeriveInput, Field, Fields};
+use syn::{
+    Attribute, Data, DeriveInput, Expr, Field, Fields, GenericParam, Generics, LitStr, Token, Type,
+    Variant, Visibility, WherePredicate, meta::ParseNestedMeta, punctuated::Punctuated,
+    spanned::Spanned,
+};
+
+use crate::utils::suggest::with_suggestion;
+
+use placeholder::{Placeholder, SEGMENT_POLICIES, SegmentPolicy};
+
+pub mod placeholder;
+
+/// Properties accepted by `#[s3_item_prop(...)]` on the item.
+const ITEM_PROPS: [&str; 5] = ["bucket", "key", "content_type", "bound", "parse"];
+/// Properties accepted by `#[s3_item_prop(...)]` on a variant of an enum item.
+const VARIANT_PROPS: [&str; 2] = ["key", "content_type"];
+/// Properties accepted by `#[s3_item_prop(...)]` on a field.
+const FIELD_PROPS: [&str; 1] = ["segment"];
+/// Longest key S3 accepts, in bytes.
+const MAX_KEY_LENGTH: usize = 1024;
 
 #[derive(Debug)]
 pub struct StructInfo {
     pub struct_name: String,
-    pub bucket: Option<String>,
+    pub visibility: Visibility,
+    pub generics: Generics,
+    /// Extra where predicates from `#[s3_item_prop(bound = "...")]`.
+    pub bounds: Vec<WherePredicate>,
+    pub bucket: Option<Expr>,
+    pub key: Option<Key>,
+    pub content_type: Option<String>,
+    pub fields: Vec<FieldInfo>,
+    /// Set by `#[s3_item_prop(parse)]`, which generates a `KeyParser`.
+    pub parse: Option<Span>,
+    /// Variants of an enum item, `None` for structs.
+    pub variants: Option<Vec<VariantInfo>>,
+}
+
+#[derive(Debug)]
+pub struct VariantInfo {
+    pub name: String,
     pub key: Option<Key>,
     pub content_type: Option<String>,
     pub fields: Vec<FieldInfo>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
 pub struct Key {
     pub value: String,
+    /// Field of every placeholder, in order.
     pub arguments: Vec<String>,
+    pub placeholders: Vec<Placeholder>,
+    pub span: Span,
 }
 
 impl Key {
-    pub fn new(mut value: String) -> Self {
-        value = value.replace("\"", "");
+    pub fn new(value: String, span: Span) -> syn::Result<Self> {
         let regex = Regex::new(r"\{([^}]*)\}").unwrap();
 
-        let finds: Vec<_> = regex.find_iter(&value).collect();
+        let mut placeholders = vec![];
+        let mut errors: Option<syn::Error> = None;
+        for captures in regex.captures_iter(&value) {
+            match Placeholder::parse(&captures[1]) {
+                Ok(x) => placeholders.push(x),
+                Err(message) => combine_error(&mut errors, syn::Error::new(span, message)),
+            }
+        }
+        if let Some(errors) = errors {
+            return Err(errors);
+        }
 
-        let mut arguments = vec![];
+        Ok(Self {
+            arguments: placeholders.iter().map(|x| x.field.clone()).collect(),
+            value,
+            placeholders,
+            span,
+        })
+    }
 
-        for find in finds {
-            arguments.push(
-                find.as_str()
-                    .trim_start_matches("{")
-                    .trim_end_matches("}")
-                    .to_string(),
-            )
+    pub fn unique_arguments(&self) -> Vec<&String> {
+        let mut unique_arguments: Vec<&String> = vec![];
+        for argument in &self.arguments {
+            if !unique_arguments.contains(&argument) {
+                unique_arguments.push(argument);
+            }
         }
+        unique_arguments
+    }
 
-        Self { value, arguments }
+    /// The key with every placeholder replaced by `{}`, as expected by `fill_key_template`.
+    pub fn template(&self) -> String {
+        let mut template = self.value.clone();
+        for placeholder in &self.placeholders {
+            template = template.replace(&format!("{{{}}}", placeholder.raw), "{}");
+        }
+        template
+    }
+
+    /// Whether every placeholder is rendered as is, without a format or transforms.
+    pub fn is_plain(&self) -> bool {
+        self.placeholders.iter().all(Placeholder::is_plain)
+    }
+
+    /// Whether keys can be parsed back into the typed key, `fields` giving the field types.
+    pub fn is_parsable(&self, fields: &[FieldInfo]) -> bool {
+        self.placeholders.iter().all(|placeholder| {
+            let field = fields.iter().find(|x| x.name == placeholder.field);
+            placeholder.is_parsable(field.map(|x| &x.ty))
+        })
     }
 
     pub fn is_static_key(&self) -> bool {
-        self.arguments.len() == 0
+        self.arguments.is_empty()
     }
 }
 
 impl StructInfo {
-    pub fn new(struct_name: String) -> Self {
+    pub fn new(struct_name: String, visibility: Visibility, generics: Generics) -> Self {
         Self {
             struct_name,
+            visibility,
+            generics,
+            bounds: vec![],
             bucket: None,
             key: None,
             content_type: None,
             fields: vec![],
+            parse: None,
+            variants: None,
         }
     }
 
-    pub fn perform_checks(&self) {
-        let Some(key) = &self.key else {
-            return;
+    pub fn perform_checks(&self) -> syn::Result<()> {
+        let mut errors: Option<syn::Error> = None;
+        if let Some(key) = &self.key {
+            check_key(key, &self.fields, &mut errors);
+        }
+
+        if let Some(span) = self.parse {
+            self.check_parse(span, &mut errors);
+        }
+
+        let variants = self.variants.iter().flatten();
+        let has_keys = variants.clone().any(|x| x.key.is_some());
+        let has_content_types = variants.clone().any(|x| x.content_type.is_some());
+        for variant in variants {
+            match &variant.key {
+                Some(key) => check_key(key, &variant.fields, &mut errors),
+                None if has_keys => {
+                    let message = format!(
+                        "Variant `{}` has no key, every variant needs one when any of them has.",
+                        variant.name
+                    );
+                    combine_error(&mut errors, syn::Error::new(variant.span, message));
+                }
+                None => {}
+            }
+            if has_content_types && variant.content_type.is_none() && self.content_type.is_none() {
+                let message = format!(
+                    "Variant `{}` has no content_type and the enum has no default content_type.",
+                    variant.name
+                );
+                combine_error(&mut errors, syn::Error::new(variant.span, message));
+            }
+        }
+        errors.map_or(Ok(()), Err)
+    }
+
+    /// Checks that the keys of an item with `#[s3_item_prop(parse)]` can be parsed.
+    fn check_parse(&self, span: Span, errors: &mut Option<syn::Error>) {
+        let keys: Vec<(&Key, &[FieldInfo])> = match &self.variants {
+            Some(variants) => variants
+                .iter()
+                .filter_map(|x| Some((x.key.as_ref()?, x.fields.as_slice())))
+                .collect(),
+            None => self
+                .key
+                .iter()
+                .map(|x| (x, self.fields.as_slice()))
+                .collect(),
         };
+        if keys.is_empty() {
+            let message = "`parse` needs a key to parse.";
+            combine_error(errors, syn::Error::new(span, message));
+        }
+        if !keys.iter().all(|(key, fields)| key.is_parsable(fields)) {
+            let message = "Keys with transforms, date formats or formats other than zero padding of integers can't be parsed back into their fields.";
+            combine_error(errors, syn::Error::new(span, message));
+        }
+    }
 
-        for argument in &key.arguments {
-            if !self.field_exists(argument) {
-                panic!("Field {argument} provided in the key does not exists.");
+    /// Types of the fields used in the key, of every variant for enums.
+    pub fn key_field_types(&self) -> Vec<&Type> {
+        let fields = std::iter::once((&self.key, &self.fields))
+            .chain(self.variants.iter().flatten().map(|x| (&x.key, &x.fields)));
+        let mut types = vec![];
+        for (key, fields) in fields {
+            let Some(key) = key else {
+                continue;
+            };
+            for argument in key.unique_arguments() {
+                if let Some(field) = fields.iter().find(|x| &x.name == argument) {
+                    types.push(&field.ty);
+                }
             }
         }
+        types
+    }
+
+    pub fn typed_key_name(&self) -> String {
+        format!("{}Key", self.struct_name)
+    }
+
+    pub fn get_field(&self, field_name: &String) -> Option<&FieldInfo> {
+        self.fields.iter().find(|x| &x.name == field_name)
+    }
+
+    /// Generics of the item with the extra bounds added to its where clause.
+    pub fn impl_generics(&self) -> Generics {
+        let mut generics = self.generics.clone();
+        generics
+            .make_where_clause()
+            .predicates
+            .extend(self.bounds.iter().cloned());
+        generics
+    }
+
+    /// Generics of the typed key: only the parameters used by the key fields, and the where
+    /// predicates that don't refer to any other parameter.
+    pub fn typed_key_generics(&self) -> Generics {
+        let mut used = HashSet::new();
+        for ty in self.key_field_types() {
+            collect_idents(ty.to_token_stream(), &mut used);
+        }
+
+        let param_name = |param: &GenericParam| match param {
+            GenericParam::Type(x) => x.ident.to_string(),
+            GenericParam::Lifetime(x) => x.lifetime.to_string(),
+            GenericParam::Const(x) => x.ident.to_string(),
+        };
+        let unused: HashSet<String> = self
+            .generics
+            .params
+            .iter()
+            .map(param_name)
+            .filter(|x| !used.contains(x))
+            .collect();
+
+        let generics = self.impl_generics();
+        let mut key_generics = Generics {
+            params: generics
+                .params
+                .into_iter()
+                .filter(|x| !unused.contains(&param_name(x)))
+                .collect(),
+            ..Default::default()
+        };
+        let predicates = generics
+            .where_clause
+            .into_iter()
+            .flat_map(|x| x.predicates)
+            .filter(|x| {
+                let mut idents = HashSet::new();
+                collect_idents(x.to_token_stream(), &mut idents);
+                idents.is_disjoint(&unused)
+            });
+        key_generics
+            .make_where_clause()
+            .predicates
+            .extend(predicates);
+        key_generics
     }
 
-    pub fn field_exists(&self, field_name: &String) -> bool {
-        self.fields.iter().any(|x| &x.name == field_name)
+    fn parse_attribute(&mut self, attribute: &Attribute) -> syn::Result<()> {
+        let is_enum = self.variants.is_some();
+        parse_props(attribute, &ITEM_PROPS, |name, meta| {
+            let is_duplicate = match name {
+                "bucket" => self.bucket.is_some(),
+                "key" => self.key.is_some(),
+                "content_type" => self.content_type.is_some(),
+                "parse" => self.parse.is_some(),
+                _ => false,
+            };
+            if is_duplicate {
+                return Err(meta.error(format!("Duplicate s3_item_prop `{name}`.")));
+            }
+            if is_enum && name == "key" {
+                return Err(meta.error("Enum items declare the key on each variant."));
+            }
+            if name == "parse" {
+                self.parse = Some(meta.path.span());
+                return Ok(());
+            }
+
+            let value = meta.value()?;
+            match name {
+                "bucket" => self.bucket = Some(value.parse()?),
+                "key" => self.key = Some(Key::try_from(value.parse::<LitStr>()?)?),
+                "content_type" => self.content_type = Some(value.parse::<LitStr>()?.value()),
+                _ => self.bounds.extend(parse_bound(value.parse()?)?),
+            }
+            Ok(())
+        })
     }
+}
+
+impl VariantInfo {
+    fn parse_attribute(&mut self, attribute: &Attribute) -> syn::Result<()> {
+        parse_props(attribute, &VARIANT_PROPS, |name, meta| {
+            let is_duplicate = match name {
+                "key" => self.key.is_some(),
+                _ => self.content_type.is_some(),
+            };
+            if is_duplicate {
+                return Err(meta.error(format!("Duplicate s3_item_prop `{name}`.")));
+            }
+
+            let value = meta.value()?;
+            match name {
+                "key" => self.key = Some(Key::try_from(value.parse::<LitStr>()?)?),
+                _ => self.content_type = Some(value.parse::<LitStr>()?.value()),
+            }
+            Ok(())
+        })
+    }
+}
+
+impl TryFrom<&Variant> for VariantInfo {
+    type Error = syn::Error;
+
+    fn try_from(variant: &Variant) -> syn::Result<Self> {
+        let mut errors: Option<syn::Error> = None;
+        let fields = match &variant.fields {
+            Fields::Named(fields) => parse_fields(&fields.named, &mut errors),
+            Fields::Unit => vec![],
+            fields => {
+                return Err(syn::Error::new_spanned(
+                    fields,
+                    "Only named fields are supported.",
+                ));
+            }
+        };
+        let mut variant_info = Self {
+            name: variant.ident.to_string(),
+            key: None,
+            content_type: None,
+            fields,
+            span: variant.ident.span(),
+        };
 
-    pub fn set_content_type(&mut self, content_type: String) {
-        self.content_type = Some(content_type.replace("\"", ""))
+        for attribute in &variant.attrs {
+            if !attribute.path().is_ident("s3_item_prop") {
+                continue;
+            }
+
+            if let Err(error) = variant_info.parse_attribute(attribute) {
+                combine_error(&mut errors, error);
+            }
+        }
+        errors.map_or(Ok(variant_info), Err)
     }
+}
+
+impl TryFrom<LitStr> for Key {
+    type Error = syn::Error;
 
-    pub fn set_key(&mut self, key: String) {
-        let key = Key::new(key);
-        self.key = Some(key)
+    fn try_from(value: LitStr) -> syn::Result<Self> {
+        Key::new(value.value(), value.span())
     }
 }
 
 #[derive(Debug)]
 pub struct FieldInfo {
     pub name: String,
+    pub ty: Type,
+    /// From `#[s3_item_prop(segment = "...")]`.
+    pub segment_policy: Option<SegmentPolicy>,
 }
 
 impl FieldInfo {
-    pub fn new(name: String) -> Self {
-        Self { name }
+    pub fn new(name: String, ty: Type) -> Self {
+        Self {
+            name,
+            ty,
+            segment_policy: None,
+        }
+    }
+
+    fn parse_attribute(&mut self, attribute: &Attribute) -> syn::Result<()> {
+        parse_props(attribute, &FIELD_PROPS, |name, meta| {
+            if self.segment_policy.is_some() {
+                return Err(meta.error(format!("Duplicate s3_item_prop `{name}`.")));
+            }
+
+            let value: LitStr = meta.value()?.parse()?;
+            let Some(policy) = SegmentPolicy::parse(&value.value()) else {
+                let expected: Vec<String> =
+                    SEGMENT_POLICIES.iter().map(|x| format!("`{x}`")).collect();
+                let message = with_suggestion(
+                    format!(
+                        "Unknown segment policy `{}`, expected one of {}.",
+                        value.value(),
+                        expected.join(", ")
+                    ),
+                    &value.value(),
+                    SEGMENT_POLICIES,
+                );
+                return Err(syn::Error::new(value.span(), message));
+            };
+            self.segment_policy = Some(policy);
+            Ok(())
+        })
     }
 }
 
-impl From<DeriveInput> for StructInfo {
-    fn from(input: DeriveInput) -> Self {
-        let mut struct_info = Self::new(input.ident.to_string());
-        for attribute in input.attrs {
+impl TryFrom<DeriveInput> for StructInfo {
+    type Error = syn::Error;
+
+    fn try_from(input: DeriveInput) -> syn::Result<Self> {
+        let mut struct_info = Self::new(
+            input.ident.to_string(),
+            input.vis.clone(),
+            input.generics.clone(),
+        );
+        let mut errors: Option<syn::Error> = None;
+        match &input.data {
+            Data::Struct(data) => match &data.fields {
+                Fields::Named(fields) => {
+                    struct_info.fields = parse_fields(&fields.named, &mut errors);
+                }
+                fields => {
+                    return Err(syn::Error::new_spanned(
+                        fields,
+                        "Only named fields are supported.",
+                    ));
+                }
+            },
+            Data::Enum(data) => {
+                let mut variants = vec![];
+                for variant in &data.variants {
+                    match VariantInfo::try_from(variant) {
+                        Ok(x) => variants.push(x),
+                        Err(error) => combine_error(&mut errors, error),
+                    }
+                }
+                struct_info.variants = Some(variants);
+            }
+            Data::Union(data) => {
+                return Err(syn::Error::new_spanned(
+                    data.union_token,
+                    "Only structs and enums are supported.",
+                ));
+            }
+        }
+
+        for attribute in &input.attrs {
             if !attribute.path().is_ident("s3_item_prop") {
                 continue;
             }
 
-            let _ = attribute.parse_nested_meta(|meta| {
-                let Some(ident) = meta.path.get_ident() else {
-                    return Ok(());
-                };
+            if let Err(error) = struct_info.parse_attribute(attribute) {
+                combine_error(&mut errors, error);
+            }
+        }
 
-                match ident.to_string().as_str() {
-                    "bucket" => {
-                        let Ok(value) = meta.value() else {
-                            panic!("Error while getting pk key for struct.");
-                        };
-                        struct_info.bucket = Some(value.to_string());
-                    }
-                    "key" => {
-                        let Ok(value) = meta.value() else {
-                            panic!("Error while getting pk key value for struct.");
-                        };
-                        struct_info.set_key(value.to_string());
-                    }
-                    "content_type" => {
-                        let Ok(value) = meta.value() else {
-                            panic!("Error while getting pk key value for struct.");
-                        };
+        if let Err(error) = struct_info.perform_checks() {
+            combine_error(&mut errors, error);
+        }
+        errors.map_or(Ok(struct_info), Err)
+    }
+}
 
-                        struct_info.set_content_type(value.to_string());
-                    }
-                    _ => {}
-                }
+impl TryFrom<&Field> for FieldInfo {
+    type Error = syn::Error;
+
+    fn try_from(field: &Field) -> syn::Result<Self> {
+        let mut field_info = FieldInfo::new(
+            field.ident.as_ref().unwrap().to_string().replace("\"", ""),
+            field.ty.clone(),
+        );
+
+        let mut errors: Option<syn::Error> = None;
+        for attribute in &field.attrs {
+            if !attribute.path().is_ident("s3_item_prop") {
+                continue;
+            }
+
+            if let Err(error) = field_info.parse_attribute(attribute) {
+                combine_error(&mut errors, error);
+            }
+        }
+        errors.map_or(Ok(field_info), Err)
+    }
+}
+
+/// The where predicates of every `#[s3_item_prop(bound = "...")]` among `attributes`, skipping the
+/// other properties, which `S3BucketItem` validates.
+pub fn parse_bounds(attributes: &[Attribute]) -> syn::Result<Vec<WherePredicate>> {
+    let mut bounds = vec![];
+    for attribute in attributes {
+        if !attribute.path().is_ident("s3_item_prop") {
+            continue;
+        }
+        attribute.parse_nested_meta(|meta| {
+            if !meta.input.peek(Token![=]) {
                 return Ok(());
-            });
+            }
+            let value = meta.value()?;
+            match meta.path.is_ident("bound") {
+                true => bounds.extend(parse_bound(value.parse()?)?),
+                false => {
+                    value.parse::<Expr>()?;
+                }
+            }
+            Ok(())
+        })?;
+    }
+    Ok(bounds)
+}
+
+fn parse_bound(bound: LitStr) -> syn::Result<Punctuated<WherePredicate, Token![,]>> {
+    bound.parse_with(Punctuated::parse_terminated)
+}
+
+/// Parses one `#[s3_item_prop(...)]` attribute, rejecting the properties not in `allowed`.
+fn parse_props(
+    attribute: &Attribute,
+    allowed: &[&str],
+    mut parse_prop: impl FnMut(&str, ParseNestedMeta) -> syn::Result<()>,
+) -> syn::Result<()> {
+    attribute.parse_nested_meta(|meta| {
+        let name = meta
+            .path
+            .get_ident()
+            .map(|x| x.to_string())
+            .unwrap_or_default();
+        if !allowed.contains(&name.as_str()) {
+            let path = meta.path.to_token_stream();
+            let expected: Vec<String> = allowed.iter().map(|x| format!("`{x}`")).collect();
+            let message = with_suggestion(
+                format!(
+                    "Unknown s3_item_prop `{path}`, expected one of {}.",
+                    expected.join(", ")
+                ),
+                &name,
+                allowed.iter().copied(),
+            );
+            return Err(meta.error(message));
         }
+        parse_prop(&name, meta)
+    })
+}
 
-        let fields = if let Data::Struct(data) = input.data {
-            if let Fields::Named(fields) = data.fields {
-                fields
-            } else {
-                panic!("Only named fields are supported.");
+fn parse_fields(
+    fields: &Punctuated<Field, Token![,]>,
+    errors: &mut Option<syn::Error>,
+) -> Vec<FieldInfo> {
+    let mut field_infos = vec![];
+    for field in fields {
+        // Fields with invalid props are kept so the key checks don't report them as missing.
+        match FieldInfo::try_from(field) {
+            Ok(x) => field_infos.push(x),
+            Err(error) => {
+                combine_error(errors, error);
+                field_infos.push(FieldInfo::new(
+                    field.ident.as_ref().unwrap().to_string(),
+                    field.ty.clone(),
+                ));
             }
-        } else {
-            panic!("Only structs are supported.");
-        };
+        }
+    }
+    field_infos
+}
 
-        for field in fields.named.iter() {
-            struct_info.fields.push(FieldInfo::from(field));
+/// Checks that every placeholder of `key` names one of `fields`, and that the static parts of the
+/// key alone don't exceed the length S3 accepts.
+fn check_key(key: &Key, fields: &[FieldInfo], errors: &mut Option<syn::Error>) {
+    let static_length = key.template().replace("{}", "").len();
+    if static_length > MAX_KEY_LENGTH {
+        let message = format!(
+            "The key is at least {static_length} bytes long, S3 allows at most {MAX_KEY_LENGTH}."
+        );
+        combine_error(errors, syn::Error::new(key.span, message));
+    }
+    for argument in key.unique_arguments() {
+        if fields.iter().any(|x| &x.name == argument) {
+            continue;
+        }
+        if argument.is_empty() {
+            let message = "Empty placeholder `{}` in the key, placeholders must name a field.";
+            combine_error(errors, syn::Error::new(key.span, message));
+            continue;
         }
-        struct_info.perform_checks();
-        return struct_info;
+        let message = with_suggestion(
+            format!("Field `{argument}` provided in the key does not exist."),
+            argument,
+            fields.iter().map(|x| x.name.as_str()),
+        );
+        combine_error(errors, syn::Error::new(key.span, message));
+    }
+}
+
+fn combine_error(errors: &mut Option<syn::Error>, error: syn::Error) {
+    match errors {
+        Some(errors) => errors.combine(error),
+        None => *errors = Some(error),
     }
 }
 
-impl From<&Field> for FieldInfo {
-    fn from(field: &Field) -> Self {
-        let field_info =
-            FieldInfo::new(field.ident.as_ref().unwrap().to_string().replace("\"", ""));
-        return field_info;
+/// Collects every identifier and lifetime in `tokens`, e.g. the generic parameters a type uses.
+fn collect_idents(tokens: TokenStream, idents: &mut HashSet<String>) {
+    let mut is_lifetime = false;
+    for token in tokens {
+        match &token {
+            TokenTree::Ident(x) if is_lifetime => {
+                idents.insert(format!("'{x}"));
+            }
+            TokenTree::Ident(x) => {
+                idents.insert(x.to_string());
+            }
+            TokenTree::Group(x) => collect_idents(x.stream(), idents),
+            _ => {}
+        }
+        is_lifetime = matches!(&token, TokenTree::Punct(x) if x.as_char() == '\'');
     }
 }
@@ -0,0 +1,221 @@
+use regex::Regex;
+use syn::Type;
+
+use crate::utils::suggest::with_suggestion;
+
+/// Transforms accepted after `|` in a placeholder.
+const TRANSFORMS: [&str; 4] = ["lower", "upper", "slug", "sha256"];
+/// `strftime` specifiers supported by `s3_bucket::key_format::format_key_date`.
+const DATE_SPECIFIERS: [char; 9] = ['Y', 'y', 'm', 'd', 'j', 'H', 'M', 'S', '%'];
+
+/// Integer types, whose zero padded output parses back into the same value.
+const INTEGER_TYPES: [&str; 12] = [
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+/// Values accepted by `#[s3_item_prop(segment = "...")]` on a field.
+pub const SEGMENT_POLICIES: [&str; 3] = ["reject", "percent_encode", "allow_slash"];
+
+/// Mirrors `s3_bucket::traits::key_builder::SegmentPolicy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentPolicy {
+    Reject,
+    PercentEncode,
+    AllowSlash,
+}
+
+impl SegmentPolicy {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "reject" => Some(Self::Reject),
+            "percent_encode" => Some(Self::PercentEncode),
+            "allow_slash" => Some(Self::AllowSlash),
+            _ => None,
+        }
+    }
+
+    pub fn variant_name(self) -> &'static str {
+        match self {
+            Self::Reject => "Reject",
+            Self::PercentEncode => "PercentEncode",
+            Self::AllowSlash => "AllowSlash",
+        }
+    }
+}
+
+/// One `{field:format|transform}` placeholder of a key template.
+#[derive(Debug)]
+pub struct Placeholder {
+    /// The text between the braces.
+    pub raw: String,
+    pub field: String,
+    pub format: Option<SegmentFormat>,
+    pub transforms: Vec<Transform>,
+}
+
+#[derive(Debug)]
+pub enum SegmentFormat {
+    /// A `format!` spec such as `08` or `>10`, applied to the `Display` output of the field.
+    Std(String),
+    /// A `strftime` format such as `%Y/%m/%d` for date fields.
+    Date(String),
+}
+
+#[derive(Debug)]
+pub struct Transform {
+    pub kind: TransformKind,
+    /// Range of characters kept from the output, e.g. `[..2]`.
+    pub slice: Option<(usize, Option<usize>)>,
+}
+
+#[derive(Debug)]
+pub enum TransformKind {
+    Lower,
+    Upper,
+    Slug,
+    Sha256,
+}
+
+impl Placeholder {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let mut transforms = raw.split('|');
+        let field_and_format = transforms.next().unwrap_or_default();
+        let (field, format) = match field_and_format.split_once(':') {
+            Some((field, format)) => (field, Some(SegmentFormat::parse(field, format)?)),
+            None => (field_and_format, None),
+        };
+        let field = field.trim().to_string();
+        let transforms = transforms
+            .map(|x| Transform::parse(&field, x))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            raw: raw.to_string(),
+            field,
+            format,
+            transforms,
+        })
+    }
+
+    pub fn is_plain(&self) -> bool {
+        self.format.is_none() && self.transforms.is_empty()
+    }
+
+    /// Policy of the field, `AllowSlash` by default so fields holding paths keep working.
+    pub fn policy(&self, field_policy: Option<SegmentPolicy>) -> SegmentPolicy {
+        field_policy.unwrap_or(SegmentPolicy::AllowSlash)
+    }
+
+    /// Whether the value of a field of type `ty` can be recovered from the rendered segment. Of
+    /// the formats, only zero padding of integers such as `{id:08}` reads back into the same value.
+    pub fn is_parsable(&self, ty: Option<&Type>) -> bool {
+        if !self.transforms.is_empty() {
+            return false;
+        }
+        match &self.format {
+            None => true,
+            Some(SegmentFormat::Date(_)) => false,
+            Some(SegmentFormat::Std(format)) => {
+                let is_zero_padding = format.len() > 1
+                    && format.starts_with('0')
+                    && format.chars().all(|x| x.is_ascii_digit());
+                is_zero_padding && ty.is_some_and(is_integer)
+            }
+        }
+    }
+}
+
+impl SegmentFormat {
+    fn parse(field: &str, format: &str) -> Result<Self, String> {
+        if format.is_empty() {
+            return Err(format!("Empty format for `{field}` in the key."));
+        }
+        if format.contains('%') {
+            let mut chars = format.chars();
+            while let Some(c) = chars.next() {
+                if c != '%' {
+                    continue;
+                }
+                match chars.next() {
+                    Some(x) if DATE_SPECIFIERS.contains(&x) => {}
+                    specifier => {
+                        let specifier = specifier.map(String::from).unwrap_or_default();
+                        let supported: Vec<String> =
+                            DATE_SPECIFIERS.iter().map(|x| format!("`%{x}`")).collect();
+                        return Err(format!(
+                            "Unsupported date specifier `%{specifier}` for `{field}` in the key, expected one of {}.",
+                            supported.join(", ")
+                        ));
+                    }
+                }
+            }
+            return Ok(Self::Date(format.to_string()));
+        }
+
+        // Type specifiers like `x` need more than `Display`, which is all the key builder has.
+        let regex = Regex::new(r"^([^{}]?[<^>])?[+-]?#?0?\d*(\.\d+)?$").unwrap();
+        if !regex.is_match(format) {
+            return Err(format!(
+                "Invalid format `{format}` for `{field}` in the key, expected fill, alignment, sign, width and precision, e.g. `08` or `>10`, or a date format such as `%Y/%m/%d`."
+            ));
+        }
+        Ok(Self::Std(format.to_string()))
+    }
+}
+
+impl Transform {
+    fn parse(field: &str, transform: &str) -> Result<Self, String> {
+        let regex = Regex::new(r"^([^\[]*)(?:\[(\d*)\.\.(\d*)\])?$").unwrap();
+        let invalid = || {
+            format!(
+                "Invalid slice in transform `{transform}` of `{field}` in the key, expected e.g. `[..2]` or `[1..3]`."
+            )
+        };
+        let Some(captures) = regex.captures(transform) else {
+            return Err(invalid());
+        };
+
+        let name = captures[1].trim();
+        let kind = match name {
+            "lower" => TransformKind::Lower,
+            "upper" => TransformKind::Upper,
+            "slug" => TransformKind::Slug,
+            "sha256" => TransformKind::Sha256,
+            _ => {
+                let expected: Vec<String> = TRANSFORMS.iter().map(|x| format!("`{x}`")).collect();
+                return Err(with_suggestion(
+                    format!(
+                        "Unknown transform `{name}` of `{field}` in the key, expected one of {}.",
+                        expected.join(", ")
+                    ),
+                    name,
+                    TRANSFORMS,
+                ));
+            }
+        };
+
+        let slice = match (captures.get(2), captures.get(3)) {
+            (Some(start), Some(end)) => {
+                let start = start.as_str().parse().unwrap_or(0);
+                let end: Option<usize> = end.as_str().parse().ok();
+                if end.is_some_and(|x| x <= start) {
+                    return Err(invalid());
+                }
+                Some((start, end))
+            }
+            _ => None,
+        };
+        Ok(Self { kind, slice })
+    }
+}
+
+fn is_integer(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    path.qself.is_none()
+        && path
+            .path
+            .get_ident()
+            .is_some_and(|x| INTEGER_TYPES.contains(&x.to_string().as_str()))
+}
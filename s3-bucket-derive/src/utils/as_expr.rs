@@ -1,5 +1,4 @@
-use syn::{parse_str, Expr};
-
+use syn::{Expr, parse_str};
 
 pub trait AsExpr {
     fn as_expr(&self) -> Expr;
@@ -12,4 +11,4 @@ impl AsExpr for String {
             Err(e) => panic!("Error while converting string `{}` to expr : {}", self, e),
         }
     }
-}
\ No newline at end of file
+}
@@ -1 +1,2 @@
-pub mod as_expr;
\ No newline at end of file
+pub mod as_expr;
+pub mod suggest;
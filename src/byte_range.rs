@@ -0,0 +1,93 @@
+use std::ops::{Range, RangeFrom, RangeInclusive};
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// Bytes from `start` to `end`, both inclusive.
+    Inclusive { start: u64, end: u64 },
+    /// Bytes from `start` to the end of the object.
+    From { start: u64 },
+    /// The last `length` bytes of the object.
+    Last { length: u64 },
+}
+
+impl ByteRange {
+    pub fn last(length: u64) -> Self {
+        Self::Last { length }
+    }
+
+    pub fn to_header(&self) -> String {
+        match self {
+            ByteRange::Inclusive { start, end } => format!("bytes={start}-{end}"),
+            ByteRange::From { start } => format!("bytes={start}-"),
+            ByteRange::Last { length } => format!("bytes=-{length}"),
+        }
+    }
+}
+
+/// Fails for empty ranges, which a `Range` header can't express: S3 ignores a header like
+/// `bytes=5-4` and returns the whole object.
+impl TryFrom<Range<u64>> for ByteRange {
+    type Error = Error;
+
+    fn try_from(value: Range<u64>) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Err(Error::EmptyRange(format!("{value:?}")));
+        }
+        Ok(Self::Inclusive {
+            start: value.start,
+            end: value.end - 1,
+        })
+    }
+}
+
+impl TryFrom<RangeInclusive<u64>> for ByteRange {
+    type Error = Error;
+
+    fn try_from(value: RangeInclusive<u64>) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Err(Error::EmptyRange(format!("{value:?}")));
+        }
+        Ok(Self::Inclusive {
+            start: *value.start(),
+            end: *value.end(),
+        })
+    }
+}
+
+impl From<RangeFrom<u64>> for ByteRange {
+    fn from(value: RangeFrom<u64>) -> Self {
+        Self::From { start: value.start }
+    }
+}
+
+/// Ranges accepted by `get_range`: a `ByteRange`, or a `Range`, `RangeInclusive` or `RangeFrom` of
+/// byte offsets.
+pub trait IntoByteRange {
+    fn into_byte_range(self) -> Result<ByteRange, Error>;
+}
+
+impl IntoByteRange for ByteRange {
+    fn into_byte_range(self) -> Result<ByteRange, Error> {
+        Ok(self)
+    }
+}
+
+impl IntoByteRange for Range<u64> {
+    fn into_byte_range(self) -> Result<ByteRange, Error> {
+        self.try_into()
+    }
+}
+
+impl IntoByteRange for RangeInclusive<u64> {
+    fn into_byte_range(self) -> Result<ByteRange, Error> {
+        self.try_into()
+    }
+}
+
+impl IntoByteRange for RangeFrom<u64> {
+    fn into_byte_range(self) -> Result<ByteRange, Error> {
+        Ok(self.into())
+    }
+}
@@ -0,0 +1,29 @@
+/// Outcome of a batch delete.
+#[derive(Debug, Clone, Default)]
+pub struct DeleteReport {
+    /// Every key the delete was asked for. For dry runs these are the keys that would have been
+    /// deleted, while `deleted` stays empty.
+    pub matched: Vec<String>,
+    pub deleted: Vec<String>,
+    pub failures: Vec<DeleteFailure>,
+}
+
+impl DeleteReport {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn extend(&mut self, other: DeleteReport) {
+        self.matched.extend(other.matched);
+        self.deleted.extend(other.deleted);
+        self.failures.extend(other.failures);
+    }
+}
+
+/// A key which could not be deleted, with the error code and message reported by the store.
+#[derive(Debug, Clone)]
+pub struct DeleteFailure {
+    pub key: String,
+    pub code: Option<String>,
+    pub message: Option<String>,
+}
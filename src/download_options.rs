@@ -0,0 +1,28 @@
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadOptions {
+    /// Size of every ranged GET when downloading in parallel.
+    pub part_size: u64,
+    /// Number of ranged GETs in flight. A value of 1 downloads with a single GET.
+    pub concurrency: usize,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            part_size: 8 * 1024 * 1024,
+            concurrency: 1,
+        }
+    }
+}
+
+impl DownloadOptions {
+    pub fn with_part_size(mut self, part_size: u64) -> Self {
+        self.part_size = part_size;
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+}
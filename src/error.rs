@@ -1,33 +1,371 @@
+use std::{io::ErrorKind, time::Duration};
+
 use aws_sdk_s3::{
     config::http::HttpResponse,
-    error::SdkError,
+    error::{ProvideErrorMetadata, SdkError},
     operation::{
-        delete_object::DeleteObjectError, get_object::GetObjectError, put_object::PutObjectError,
+        abort_multipart_upload::AbortMultipartUploadError,
+        complete_multipart_upload::CompleteMultipartUploadError, copy_object::CopyObjectError,
+        create_multipart_upload::CreateMultipartUploadError, delete_object::DeleteObjectError,
+        delete_objects::DeleteObjectsError, get_object::GetObjectError,
+        head_object::HeadObjectError, list_objects_v2::ListObjectsV2Error,
+        put_object::PutObjectError, upload_part::UploadPartError,
+        upload_part_copy::UploadPartCopyError,
     },
     presigning::PresigningConfigError,
+    primitives::ByteStreamError,
 };
 use thiserror::Error;
 
+/// Error raised by item conversions, kept as the source of `TryIntoByteError` / `TryFromByteError`.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("Error during put operation.")]
-    PutError(SdkError<PutObjectError, HttpResponse>),
-    #[error("Error during get operation.")]
-    GetError(SdkError<GetObjectError, HttpResponse>),
-    #[error("Error during delete operation.")]
-    DeleteError(SdkError<DeleteObjectError, HttpResponse>),
-    #[error("Error while trying to convert into byte stream.")]
-    TryIntoByteError,
-    #[error("Error while trying to convert from byte stream.")]
-    TryFromByteError,
+    #[error("Error while putting key `{key}` into bucket `{bucket}`.")]
+    PutError {
+        bucket: String,
+        key: String,
+        source: Box<SdkError<PutObjectError, HttpResponse>>,
+    },
+    #[error("Precondition failed while accessing key `{key}` in bucket `{bucket}`.")]
+    PreconditionFailed { bucket: String, key: String },
+    #[error("Object `{key}` in bucket `{bucket}` has no ETag.")]
+    MissingEtag { bucket: String, key: String },
+    #[error("Updating `{key}` in bucket `{bucket}` changed its key to `{new_key}`.")]
+    KeyChanged {
+        bucket: String,
+        key: String,
+        new_key: String,
+    },
+    #[error("Error while creating multipart upload of key `{key}` in bucket `{bucket}`.")]
+    CreateMultipartUploadError {
+        bucket: String,
+        key: String,
+        source: Box<SdkError<CreateMultipartUploadError, HttpResponse>>,
+    },
+    #[error("Error while uploading part of key `{key}` in bucket `{bucket}`.")]
+    UploadPartError {
+        bucket: String,
+        key: String,
+        source: Box<SdkError<UploadPartError, HttpResponse>>,
+    },
+    #[error("Error while completing multipart upload of key `{key}` in bucket `{bucket}`.")]
+    CompleteMultipartUploadError {
+        bucket: String,
+        key: String,
+        source: Box<SdkError<CompleteMultipartUploadError, HttpResponse>>,
+    },
+    #[error("Error while aborting multipart upload of key `{key}` in bucket `{bucket}`.")]
+    AbortMultipartUploadError {
+        bucket: String,
+        key: String,
+        source: Box<SdkError<AbortMultipartUploadError, HttpResponse>>,
+    },
+    #[error("Error while copying to key `{key}` in bucket `{bucket}`.")]
+    CopyError {
+        bucket: String,
+        key: String,
+        source: Box<SdkError<CopyObjectError, HttpResponse>>,
+    },
+    #[error("Error while copying part to key `{key}` in bucket `{bucket}`.")]
+    UploadPartCopyError {
+        bucket: String,
+        key: String,
+        source: Box<SdkError<UploadPartCopyError, HttpResponse>>,
+    },
+    #[error("Error while getting key `{key}` from bucket `{bucket}`.")]
+    GetError {
+        bucket: String,
+        key: String,
+        source: Box<SdkError<GetObjectError, HttpResponse>>,
+    },
+    #[error("Error while reading metadata of key `{key}` in bucket `{bucket}`.")]
+    HeadError {
+        bucket: String,
+        key: String,
+        source: Box<SdkError<HeadObjectError, HttpResponse>>,
+    },
+    #[error("Error while deleting key `{key}` from bucket `{bucket}`.")]
+    DeleteError {
+        bucket: String,
+        key: String,
+        source: Box<SdkError<DeleteObjectError, HttpResponse>>,
+    },
+    #[error("Error while deleting keys from bucket `{bucket}`.")]
+    DeleteObjectsError {
+        bucket: String,
+        source: Box<SdkError<DeleteObjectsError, HttpResponse>>,
+    },
+    #[error("Error while listing prefix `{prefix}` in bucket `{bucket}`.")]
+    ListError {
+        bucket: String,
+        prefix: String,
+        source: Box<SdkError<ListObjectsV2Error, HttpResponse>>,
+    },
+    #[error("Error while converting the item for key `{key}` in bucket `{bucket}` into bytes.")]
+    TryIntoByteError {
+        key: String,
+        bucket: String,
+        source: BoxError,
+    },
+    #[error("Error while converting object `{key}` in bucket `{bucket}` from bytes.")]
+    TryFromByteError {
+        key: String,
+        bucket: String,
+        source: BoxError,
+    },
     #[error("Empty byte stream encountered while operforming get operation.")]
     EmptyByteStream,
     #[error("Error while converting byte array to string.")]
     ByteArrayToString,
     #[error("Error while collecting bytes from ByteStream.")]
-    ByteStreamCollectionError,
+    ByteStreamCollectionError(#[source] ByteStreamError),
     #[error("Error while generating presigning config.")]
-    PresigningConfigError(PresigningConfigError),
+    PresigningConfigError(#[source] PresigningConfigError),
+    #[error("Key `{key}` does not match the template `{template}`: {reason}.")]
+    KeyMismatch {
+        key: String,
+        template: String,
+        reason: String,
+    },
+    #[error("Error while parsing `{value}` into field `{field}` of key `{key}`.")]
+    KeySegmentParseError {
+        key: String,
+        field: String,
+        value: String,
+    },
+    #[error("Expected {expected} bytes for key `{key}` but received {actual}.")]
+    SizeMismatch {
+        key: String,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("Byte range `{0}` is empty.")]
+    EmptyRange(String),
+    #[error("Key `{key}` is not valid: {reason}.")]
+    InvalidKey { key: String, reason: String },
+    #[error("Object `{key}` does not exist in bucket `{bucket}`.")]
+    NotFound { bucket: String, key: String },
+    #[error("Operation not supported by this object store: {0}.")]
+    Unsupported(String),
+    #[error("IO error.")]
+    IoError(#[source] std::io::Error),
+    #[error("Operation did not complete within {0:?}.")]
+    Timeout(Duration),
+    #[error("Neither a client nor an object store was provided to the S3Context builder.")]
+    MissingObjectStore,
+    #[error(
+        "Presigned posts need a credentials provider, see `AwsObjectStore::with_credentials_provider`."
+    )]
+    MissingCredentialsProvider,
+    #[error(transparent)]
+    KeyBuildError(KeyBuildError),
     #[error("{0}")]
-    Other(String)
+    Other(String),
+}
+
+/// Why a key couldn't be built from the values of its placeholders.
+#[derive(Debug, Error)]
+pub enum KeyBuildError {
+    #[error("Value `{value}` of `{field}` can't be used in a key: {reason}.")]
+    InvalidSegment {
+        field: String,
+        value: String,
+        reason: String,
+    },
+    #[error("Key is {length} bytes long, S3 allows at most {max_length}.")]
+    TooLong {
+        key: String,
+        length: usize,
+        max_length: usize,
+    },
+    #[error("Keys can't be empty.")]
+    Empty,
+}
+
+impl Error {
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Error::NotFound { .. } => true,
+            Error::IoError(e) => e.kind() == ErrorKind::NotFound,
+            _ => self.sdk_error_details().is_some_and(|x| {
+                x.status == Some(404)
+                    || matches!(
+                        x.code,
+                        Some("NoSuchKey" | "NotFound" | "NoSuchBucket" | "NoSuchUpload")
+                    )
+            }),
+        }
+    }
+
+    pub fn is_access_denied(&self) -> bool {
+        match self {
+            Error::IoError(e) => e.kind() == ErrorKind::PermissionDenied,
+            _ => self.sdk_error_details().is_some_and(|x| {
+                x.status == Some(403)
+                    || matches!(
+                        x.code,
+                        Some("AccessDenied" | "AllAccessDisabled" | "InvalidAccessKeyId")
+                    )
+            }),
+        }
+    }
+
+    pub fn is_throttling(&self) -> bool {
+        self.sdk_error_details().is_some_and(|x| {
+            x.status == Some(429)
+                || matches!(
+                    x.code,
+                    Some(
+                        "SlowDown"
+                            | "Throttling"
+                            | "ThrottlingException"
+                            | "ThrottledException"
+                            | "RequestThrottled"
+                            | "RequestLimitExceeded"
+                            | "TooManyRequestsException"
+                    )
+                )
+        })
+    }
+
+    pub fn is_precondition_failed(&self) -> bool {
+        match self {
+            Error::PreconditionFailed { .. } => true,
+            _ => self
+                .sdk_error_details()
+                .is_some_and(|x| x.is_precondition_failed()),
+        }
+    }
+
+    /// Whether the operation may succeed when retried: throttling, timeouts, connection failures
+    /// and server side errors.
+    pub fn is_retryable(&self) -> bool {
+        if self.is_throttling() {
+            return true;
+        }
+        match self {
+            Error::Timeout(_) => true,
+            Error::IoError(e) => matches!(
+                e.kind(),
+                ErrorKind::TimedOut | ErrorKind::Interrupted | ErrorKind::ConnectionReset
+            ),
+            _ => self.sdk_error_details().is_some_and(|x| {
+                x.is_transient
+                    || x.status.is_some_and(|status| status >= 500)
+                    || matches!(
+                        x.code,
+                        Some("InternalError" | "ServiceUnavailable" | "RequestTimeout")
+                    )
+            }),
+        }
+    }
+
+    /// Key the failed operation was working on, if known.
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Error::PutError { key, .. }
+            | Error::PreconditionFailed { key, .. }
+            | Error::MissingEtag { key, .. }
+            | Error::KeyChanged { key, .. }
+            | Error::CreateMultipartUploadError { key, .. }
+            | Error::UploadPartError { key, .. }
+            | Error::CompleteMultipartUploadError { key, .. }
+            | Error::AbortMultipartUploadError { key, .. }
+            | Error::CopyError { key, .. }
+            | Error::UploadPartCopyError { key, .. }
+            | Error::GetError { key, .. }
+            | Error::HeadError { key, .. }
+            | Error::DeleteError { key, .. }
+            | Error::TryIntoByteError { key, .. }
+            | Error::TryFromByteError { key, .. }
+            | Error::KeyMismatch { key, .. }
+            | Error::KeySegmentParseError { key, .. }
+            | Error::SizeMismatch { key, .. }
+            | Error::InvalidKey { key, .. }
+            | Error::NotFound { key, .. } => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Bucket the failed operation was working on, if known.
+    pub fn bucket(&self) -> Option<&str> {
+        match self {
+            Error::PutError { bucket, .. }
+            | Error::PreconditionFailed { bucket, .. }
+            | Error::MissingEtag { bucket, .. }
+            | Error::KeyChanged { bucket, .. }
+            | Error::CreateMultipartUploadError { bucket, .. }
+            | Error::UploadPartError { bucket, .. }
+            | Error::CompleteMultipartUploadError { bucket, .. }
+            | Error::AbortMultipartUploadError { bucket, .. }
+            | Error::CopyError { bucket, .. }
+            | Error::UploadPartCopyError { bucket, .. }
+            | Error::GetError { bucket, .. }
+            | Error::HeadError { bucket, .. }
+            | Error::DeleteError { bucket, .. }
+            | Error::DeleteObjectsError { bucket, .. }
+            | Error::ListError { bucket, .. }
+            | Error::TryIntoByteError { bucket, .. }
+            | Error::TryFromByteError { bucket, .. }
+            | Error::NotFound { bucket, .. } => Some(bucket),
+            _ => None,
+        }
+    }
+
+    fn sdk_error_details(&self) -> Option<SdkErrorDetails<'_>> {
+        let details = match self {
+            Error::PutError { source, .. } => SdkErrorDetails::new(source),
+            Error::CreateMultipartUploadError { source, .. } => SdkErrorDetails::new(source),
+            Error::UploadPartError { source, .. } => SdkErrorDetails::new(source),
+            Error::CompleteMultipartUploadError { source, .. } => SdkErrorDetails::new(source),
+            Error::AbortMultipartUploadError { source, .. } => SdkErrorDetails::new(source),
+            Error::CopyError { source, .. } => SdkErrorDetails::new(source),
+            Error::UploadPartCopyError { source, .. } => SdkErrorDetails::new(source),
+            Error::GetError { source, .. } => SdkErrorDetails::new(source),
+            Error::HeadError { source, .. } => SdkErrorDetails::new(source),
+            Error::DeleteError { source, .. } => SdkErrorDetails::new(source),
+            Error::DeleteObjectsError { source, .. } => SdkErrorDetails::new(source),
+            Error::ListError { source, .. } => SdkErrorDetails::new(source),
+            _ => return None,
+        };
+        Some(details)
+    }
+}
+
+/// The parts of an `SdkError` used to classify it, independent of the operation.
+pub(crate) struct SdkErrorDetails<'a> {
+    code: Option<&'a str>,
+    status: Option<u16>,
+    /// Timeouts and failures to send the request or read the response.
+    is_transient: bool,
+}
+
+impl<'a> SdkErrorDetails<'a> {
+    pub(crate) fn new<E: ProvideErrorMetadata>(error: &'a SdkError<E, HttpResponse>) -> Self {
+        let is_transient = match error {
+            SdkError::TimeoutError(_) | SdkError::ResponseError(_) => true,
+            SdkError::DispatchFailure(e) => e.is_io() || e.is_timeout(),
+            _ => false,
+        };
+        let code = match error {
+            SdkError::ServiceError(e) => e.err().code(),
+            _ => None,
+        };
+
+        Self {
+            code,
+            status: error.raw_response().map(|x| x.status().as_u16()),
+            is_transient,
+        }
+    }
+
+    pub(crate) fn is_precondition_failed(&self) -> bool {
+        self.status == Some(412)
+            || matches!(
+                self.code,
+                Some("PreconditionFailed" | "ConditionalRequestConflict")
+            )
+    }
 }
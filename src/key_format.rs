@@ -0,0 +1,304 @@
+//! Helpers behind the format specifiers and transforms of key templates, e.g.
+//! `{created_at:%Y/%m/%d}` or `{user_id|sha256[..2]}`.
+
+use sha2::{Digest, Sha256};
+
+/// Calendar and clock values of a date, as rendered in keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyDateParts {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    /// Day of the year, starting at 1.
+    pub ordinal: u16,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl KeyDateParts {
+    /// Reads a date displayed as `YYYY-MM-DD`, optionally followed by ` HH:MM:SS` or `THH:MM:SS`
+    /// and e.g. fractional seconds or an offset, as the date types of chrono and time display. The
+    /// year may be signed and have more than four digits, e.g. `-0001` or `+10000`, and the hour
+    /// may have a single digit.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (year, bytes) = parse_year(value)?;
+        let number = |start: usize, end: usize| -> Option<u16> {
+            let digits = bytes.get(start..end)?;
+            digits
+                .iter()
+                .all(u8::is_ascii_digit)
+                .then(|| digits.iter().fold(0, |x, y| x * 10 + u16::from(y - b'0')))
+        };
+        let separator =
+            |index: usize, expected: &[u8]| bytes.get(index).is_some_and(|x| expected.contains(x));
+
+        if !separator(0, b"-") || !separator(3, b"-") {
+            return None;
+        }
+        let month = u8::try_from(number(1, 3)?).ok()?;
+        let day = u8::try_from(number(4, 6)?).ok()?;
+        let ordinal = ordinal(year, month, day)?;
+
+        // time displays the hour without padding.
+        let hour_end = if separator(8, b":") { 8 } else { 9 };
+        let (hour, minute, second) = match bytes.len() {
+            6 => (0, 0, 0),
+            _ if separator(6, b" T")
+                && separator(hour_end, b":")
+                && separator(hour_end + 3, b":") =>
+            {
+                (
+                    number(7, hour_end)?,
+                    number(hour_end + 1, hour_end + 3)?,
+                    number(hour_end + 4, hour_end + 6)?,
+                )
+            }
+            _ => return None,
+        };
+        if hour > 23 || minute > 59 || second > 60 {
+            return None;
+        }
+
+        Some(Self {
+            year,
+            month,
+            day,
+            ordinal,
+            hour: hour as u8,
+            minute: minute as u8,
+            second: second as u8,
+        })
+    }
+}
+
+/// Rendered as `YYYY-MM-DD HH:MM:SS`, which `KeyDateParts::parse` reads back.
+impl std::fmt::Display for KeyDateParts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{:02}-{:02} {:02}:{:02}:{:02}",
+            Year(self.year),
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second
+        )
+    }
+}
+
+/// A year with at least four digits, signed when outside of `0..=9999` as chrono and time display
+/// it, e.g. `-0001` or `+10000`.
+struct Year(i32);
+
+impl std::fmt::Display for Year {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            0..=9999 => write!(f, "{:04}", self.0),
+            _ => write!(f, "{:+05}", self.0),
+        }
+    }
+}
+
+/// Splits the leading year off `value`: an optional sign and at least four digits.
+fn parse_year(value: &str) -> Option<(i32, &[u8])> {
+    let bytes = value.as_bytes();
+    let (negative, start) = match bytes.first()? {
+        b'-' => (true, 1),
+        b'+' => (false, 1),
+        _ => (false, 0),
+    };
+    let length = bytes[start..]
+        .iter()
+        .take_while(|x| x.is_ascii_digit())
+        .count();
+    if length < 4 {
+        return None;
+    }
+    let end = start + length;
+    let magnitude: i32 = value[start..end].parse().ok()?;
+    let year = if negative { -magnitude } else { magnitude };
+    Some((year, &bytes[end..]))
+}
+
+/// Day of the year of a valid date.
+fn ordinal(year: i32, month: u8, day: u8) -> Option<u16> {
+    const DAYS: [u8; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    let is_leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in = |month: usize| u16::from(DAYS[month]) + u16::from(month == 1 && is_leap);
+
+    let month = usize::from(month).checked_sub(1).filter(|x| *x < 12)?;
+    if day == 0 || u16::from(day) > days_in(month) {
+        return None;
+    }
+    Some((0..month).map(days_in).sum::<u16>() + u16::from(day))
+}
+
+/// Types usable with a date format such as `{created_at:%Y/%m/%d}` in a key template.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be formatted as a date in a key",
+    note = "enable the `chrono` or `time` feature of s3-bucket to use their date types"
+)]
+pub trait KeyDate {
+    fn key_date_parts(&self) -> KeyDateParts;
+}
+
+impl KeyDate for KeyDateParts {
+    fn key_date_parts(&self) -> KeyDateParts {
+        *self
+    }
+}
+
+/// Renders `value` according to `format`, which supports the `strftime` specifiers `%Y`, `%y`,
+/// `%m`, `%d`, `%j`, `%H`, `%M`, `%S` and `%%`. Other specifiers are kept as they are. Like
+/// chrono, `%Y` signs years outside of `0..=9999`.
+pub fn format_key_date(value: &impl KeyDate, format: &str) -> String {
+    let parts = value.key_date_parts();
+    let mut output = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => output.push_str(&Year(parts.year).to_string()),
+            Some('y') => output.push_str(&format!("{:02}", parts.year.rem_euclid(100))),
+            Some('m') => output.push_str(&format!("{:02}", parts.month)),
+            Some('d') => output.push_str(&format!("{:02}", parts.day)),
+            Some('j') => output.push_str(&format!("{:03}", parts.ordinal)),
+            Some('H') => output.push_str(&format!("{:02}", parts.hour)),
+            Some('M') => output.push_str(&format!("{:02}", parts.minute)),
+            Some('S') => output.push_str(&format!("{:02}", parts.second)),
+            Some(c) => {
+                output.push('%');
+                if c != '%' {
+                    output.push(c);
+                }
+            }
+            None => output.push('%'),
+        }
+    }
+    output
+}
+
+/// Like `format_key_date`, for a date only known through its `Display` output, e.g. a partial key.
+/// Values which don't read as a date are used as given, so a prefix such as `2024/05` can be
+/// passed already formatted.
+pub fn format_key_date_str(value: &str, format: &str) -> String {
+    match KeyDateParts::parse(value) {
+        Some(parts) => format_key_date(&parts, format),
+        None => value.to_string(),
+    }
+}
+
+/// Lowercase ASCII letters and digits, with every other run of characters replaced by a single `-`.
+pub fn slug(value: &str) -> String {
+    let mut output = String::new();
+    for c in value.chars() {
+        if c.is_ascii_alphanumeric() {
+            output.push(c.to_ascii_lowercase());
+        } else if !output.is_empty() && !output.ends_with('-') {
+            output.push('-');
+        }
+    }
+    if output.ends_with('-') {
+        output.pop();
+    }
+    output
+}
+
+/// Lowercase hex SHA-256 digest of `value`.
+pub fn sha256(value: &str) -> String {
+    Sha256::digest(value.as_bytes())
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect()
+}
+
+/// The characters of `value` from `start` up to `end`, clamped to its length.
+pub fn slice(value: &str, start: usize, end: Option<usize>) -> String {
+    let end = end.unwrap_or(usize::MAX);
+    value
+        .chars()
+        .skip(start)
+        .take(end.saturating_sub(start))
+        .collect()
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_dates {
+    use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+    use super::{KeyDate, KeyDateParts};
+
+    fn parts(date: &impl Datelike, time: Option<&impl Timelike>) -> KeyDateParts {
+        KeyDateParts {
+            year: date.year(),
+            month: date.month() as u8,
+            day: date.day() as u8,
+            ordinal: date.ordinal() as u16,
+            hour: time.map_or(0, |x| x.hour() as u8),
+            minute: time.map_or(0, |x| x.minute() as u8),
+            second: time.map_or(0, |x| x.second() as u8),
+        }
+    }
+
+    /// Rendered in the time zone of the value.
+    impl<Tz: TimeZone> KeyDate for DateTime<Tz> {
+        fn key_date_parts(&self) -> KeyDateParts {
+            parts(self, Some(self))
+        }
+    }
+
+    impl KeyDate for NaiveDateTime {
+        fn key_date_parts(&self) -> KeyDateParts {
+            parts(self, Some(self))
+        }
+    }
+
+    impl KeyDate for NaiveDate {
+        fn key_date_parts(&self) -> KeyDateParts {
+            parts(self, None::<&NaiveDateTime>)
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod time_dates {
+    use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
+
+    use super::{KeyDate, KeyDateParts};
+
+    fn parts(date: Date, time: Time) -> KeyDateParts {
+        KeyDateParts {
+            year: date.year(),
+            month: u8::from(date.month()),
+            day: date.day(),
+            ordinal: date.ordinal(),
+            hour: time.hour(),
+            minute: time.minute(),
+            second: time.second(),
+        }
+    }
+
+    /// Rendered at the offset of the value.
+    impl KeyDate for OffsetDateTime {
+        fn key_date_parts(&self) -> KeyDateParts {
+            parts(self.date(), self.time())
+        }
+    }
+
+    impl KeyDate for PrimitiveDateTime {
+        fn key_date_parts(&self) -> KeyDateParts {
+            parts(self.date(), self.time())
+        }
+    }
+
+    impl KeyDate for Date {
+        fn key_date_parts(&self) -> KeyDateParts {
+            parts(*self, Time::MIDNIGHT)
+        }
+    }
+}
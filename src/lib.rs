@@ -7,5 +7,18 @@ pub use bytes;
 pub use s3_bucket_derive::JsonItem;
 pub use s3_bucket_derive::S3BucketItem;
 pub use s3_context::S3Context;
+pub use s3_context::S3ContextBuilder;
+pub use serde;
 pub use serde_json;
+pub mod byte_range;
+pub mod delete_report;
+pub mod download_options;
+pub mod key_format;
+pub mod object_store;
+pub mod post_policy;
+pub mod presigned_url_options;
+pub mod retry_policy;
 pub mod s3_object;
+pub mod s3_object_meta;
+pub mod upload_options;
+pub mod versioned_item;
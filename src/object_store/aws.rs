@@ -0,0 +1,805 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use aws_sdk_s3::{
+    Client,
+    config::{
+        ProvideCredentials, SharedCredentialsProvider,
+        endpoint::{DefaultResolver, Params, ResolveEndpoint},
+    },
+    error::SdkError,
+    presigning::PresigningConfig,
+    primitives::{ByteStream, DateTime, DateTimeFormat},
+    types::{
+        CompletedMultipartUpload, CompletedPart, Delete, MetadataDirective, ObjectIdentifier,
+        ServerSideEncryption, StorageClass,
+    },
+};
+use aws_sigv4::sign;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt, stream};
+use serde_json::json;
+use tokio::task::JoinHandle;
+
+use crate::{
+    delete_report::{DeleteFailure, DeleteReport},
+    error::{Error, SdkErrorDetails},
+    post_policy::{PostPolicy, PresignedPost},
+    presigned_url_options::PresignedUrlOptions,
+    s3_object_meta::S3ObjectMeta,
+    upload_options::MAX_PARTS,
+};
+
+use super::{
+    CopyObjectRequest, GetObjectRequest, GetObjectResponse, ListObjectsPage, ObjectAttributes,
+    ObjectStore, PutObjectRequest, PutObjectResponse, UploadedPart,
+};
+
+/// Maximum number of keys accepted by a single DeleteObjects request.
+const MAX_DELETE_KEYS: usize = 1000;
+
+/// Largest object CopyObject accepts; bigger objects are copied with UploadPartCopy.
+pub const MAX_COPY_OBJECT_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+const COPY_PART_SIZE: u64 = 512 * 1024 * 1024;
+const COPY_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct AwsObjectStore {
+    pub client: Client,
+    /// Objects larger than this are copied part by part.
+    pub multipart_copy_threshold: u64,
+    /// Credentials used to sign POST policies. The client does not expose the ones it signs
+    /// requests with.
+    pub credentials_provider: Option<SharedCredentialsProvider>,
+    /// Custom endpoint of the client, which presigned posts are sent to.
+    pub endpoint_url: Option<String>,
+    /// Whether the client uses path-style addressing, for the url of presigned posts.
+    pub force_path_style: bool,
+}
+
+impl AwsObjectStore {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            multipart_copy_threshold: MAX_COPY_OBJECT_SIZE,
+            credentials_provider: None,
+            endpoint_url: None,
+            force_path_style: false,
+        }
+    }
+
+    /// Credentials used to sign presigned posts, which fail with
+    /// `Error::MissingCredentialsProvider` without them. The SDK client doesn't expose its own.
+    pub fn with_credentials_provider(
+        mut self,
+        credentials_provider: impl ProvideCredentials + 'static,
+    ) -> Self {
+        self.credentials_provider = Some(SharedCredentialsProvider::new(credentials_provider));
+        self
+    }
+
+    /// Custom endpoint presigned posts are sent to, e.g. of an S3 compatible service. It has to
+    /// match the one of the client, which the SDK doesn't expose.
+    pub fn with_endpoint_url(mut self, endpoint_url: impl Into<String>) -> Self {
+        self.endpoint_url = Some(endpoint_url.into());
+        self
+    }
+
+    /// Puts the bucket in the path of presigned post urls, like `force_path_style` of the client.
+    pub fn with_force_path_style(mut self, force_path_style: bool) -> Self {
+        self.force_path_style = force_path_style;
+        self
+    }
+
+    pub fn with_multipart_copy_threshold(mut self, multipart_copy_threshold: u64) -> Self {
+        self.multipart_copy_threshold = multipart_copy_threshold.min(MAX_COPY_OBJECT_SIZE);
+        self
+    }
+
+    /// Url of the bucket as resolved by the SDK's endpoint resolver, for the client's region and
+    /// the endpoint settings given to the store.
+    async fn bucket_url(&self, bucket: &str) -> Result<String, Error> {
+        let params = Params::builder()
+            .bucket(bucket)
+            .set_region(self.client.config().region().map(|x| x.to_string()))
+            .set_endpoint(self.endpoint_url.clone())
+            .force_path_style(self.force_path_style)
+            .build()
+            .map_err(|e| {
+                Error::Other(format!(
+                    "Could not build the endpoint parameters of bucket `{bucket}`: {e}"
+                ))
+            })?;
+
+        // Through the trait, as `DefaultResolver` has a private method of the same name.
+        let endpoint = ResolveEndpoint::resolve_endpoint(&DefaultResolver::new(), &params)
+            .await
+            .map_err(|e| {
+                Error::Other(format!(
+                    "Error while resolving the endpoint of bucket `{bucket}`: {e}"
+                ))
+            })?;
+        Ok(endpoint.url().trim_end_matches('/').to_string())
+    }
+
+    async fn copy_parts(
+        &self,
+        request: &CopyObjectRequest,
+        source: &S3ObjectMeta,
+        upload_id: &str,
+    ) -> Result<Vec<UploadedPart>, Error> {
+        let part_size = COPY_PART_SIZE.max(source.size.div_ceil(MAX_PARTS));
+        let ranges = (0..source.size)
+            .step_by(part_size as usize)
+            .map(|start| (start, (start + part_size).min(source.size) - 1));
+
+        stream::iter(ranges.enumerate())
+            .map(|(index, (start, end))| async move {
+                let part_number = index as i32 + 1;
+                let output = self
+                    .client
+                    .upload_part_copy()
+                    .bucket(&request.bucket)
+                    .key(&request.key)
+                    .upload_id(upload_id)
+                    .part_number(part_number)
+                    .copy_source(copy_source(&request.source_bucket, &request.source_key))
+                    .copy_source_range(format!("bytes={start}-{end}"))
+                    .set_copy_source_if_match(source.etag.clone())
+                    .send()
+                    .await
+                    .map_err(|source| Error::UploadPartCopyError {
+                        bucket: request.bucket.clone(),
+                        key: request.key.clone(),
+                        source: Box::new(source),
+                    })?;
+
+                Ok(UploadedPart {
+                    part_number,
+                    etag: output
+                        .copy_part_result
+                        .and_then(|x| x.e_tag)
+                        .unwrap_or_default(),
+                })
+            })
+            .buffered(COPY_CONCURRENCY)
+            .try_collect()
+            .await
+    }
+}
+
+#[async_trait]
+impl ObjectStore for AwsObjectStore {
+    async fn put_object(&self, request: PutObjectRequest) -> Result<PutObjectResponse, Error> {
+        let result = self
+            .client
+            .put_object()
+            .bucket(&request.bucket)
+            .key(&request.key)
+            .body(ByteStream::from(request.body))
+            .set_content_type(request.attributes.content_type)
+            .set_metadata(non_empty(request.attributes.metadata))
+            .set_storage_class(
+                request
+                    .attributes
+                    .storage_class
+                    .as_deref()
+                    .map(StorageClass::from),
+            )
+            .set_server_side_encryption(
+                request
+                    .attributes
+                    .server_side_encryption
+                    .as_deref()
+                    .map(ServerSideEncryption::from),
+            )
+            .set_ssekms_key_id(request.attributes.sse_kms_key_id)
+            .set_if_match(request.if_match)
+            .set_if_none_match(request.if_none_match)
+            .send()
+            .await;
+
+        match result {
+            Ok(x) => Ok(PutObjectResponse { etag: x.e_tag }),
+            Err(e) if SdkErrorDetails::new(&e).is_precondition_failed() => {
+                Err(Error::PreconditionFailed {
+                    bucket: request.bucket,
+                    key: request.key,
+                })
+            }
+            Err(source) => Err(Error::PutError {
+                bucket: request.bucket,
+                key: request.key,
+                source: Box::new(source),
+            }),
+        }
+    }
+
+    async fn get_object(
+        &self,
+        request: GetObjectRequest,
+    ) -> Result<Option<GetObjectResponse>, Error> {
+        let result = self
+            .client
+            .get_object()
+            .bucket(&request.bucket)
+            .key(&request.key)
+            .set_range(request.range.map(|x| x.to_header()))
+            .set_if_match(request.if_match)
+            .send()
+            .await;
+
+        let output = match result {
+            Ok(x) => x,
+            Err(SdkError::ServiceError(e)) if e.err().is_no_such_key() => return Ok(None),
+            Err(e) if SdkErrorDetails::new(&e).is_precondition_failed() => {
+                return Err(Error::PreconditionFailed {
+                    bucket: request.bucket,
+                    key: request.key,
+                });
+            }
+            Err(source) => {
+                return Err(Error::GetError {
+                    bucket: request.bucket,
+                    key: request.key,
+                    source: Box::new(source),
+                });
+            }
+        };
+
+        let meta = S3ObjectMeta {
+            key: request.key,
+            size: output.content_length.unwrap_or_default().max(0) as u64,
+            etag: output.e_tag,
+            content_type: output.content_type,
+            last_modified: output.last_modified,
+            metadata: output.metadata.unwrap_or_default(),
+            storage_class: output.storage_class.map(|x| x.as_str().to_string()),
+            version_id: output.version_id,
+        };
+
+        Ok(Some(GetObjectResponse {
+            body: output.body,
+            meta,
+            content_range: output.content_range,
+        }))
+    }
+
+    async fn head_object(&self, bucket: &str, key: &str) -> Result<Option<S3ObjectMeta>, Error> {
+        let result = self
+            .client
+            .head_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await;
+
+        match result {
+            Ok(x) => Ok(Some(S3ObjectMeta::from_head_object_output(
+                key.to_string(),
+                x,
+            ))),
+            Err(SdkError::ServiceError(e)) if e.err().is_not_found() => Ok(None),
+            Err(source) => Err(Error::HeadError {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                source: Box::new(source),
+            }),
+        }
+    }
+
+    async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), Error> {
+        self.client
+            .delete_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|source| Error::DeleteError {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                source: Box::new(source),
+            })
+    }
+
+    async fn copy_object(&self, request: CopyObjectRequest) -> Result<(), Error> {
+        let Some(source) = self
+            .head_object(&request.source_bucket, &request.source_key)
+            .await?
+        else {
+            return Err(Error::NotFound {
+                bucket: request.source_bucket,
+                key: request.source_key,
+            });
+        };
+
+        let attributes = request.resolve_attributes(&source);
+        if source.size <= self.multipart_copy_threshold {
+            // Only a replaced metadata directive takes the content type and metadata from the
+            // request. Storage class and encryption are never copied, so they are always sent.
+            let (directive, content_type, metadata) = match request.attributes {
+                Some(_) => (
+                    MetadataDirective::Replace,
+                    attributes.content_type,
+                    non_empty(attributes.metadata),
+                ),
+                None => (MetadataDirective::Copy, None, None),
+            };
+            return self
+                .client
+                .copy_object()
+                .bucket(&request.bucket)
+                .key(&request.key)
+                .copy_source(copy_source(&request.source_bucket, &request.source_key))
+                .metadata_directive(directive)
+                .set_content_type(content_type)
+                .set_metadata(metadata)
+                .set_storage_class(attributes.storage_class.as_deref().map(StorageClass::from))
+                .set_server_side_encryption(
+                    attributes
+                        .server_side_encryption
+                        .as_deref()
+                        .map(ServerSideEncryption::from),
+                )
+                .set_ssekms_key_id(attributes.sse_kms_key_id)
+                .send()
+                .await
+                .map(|_| ())
+                .map_err(|source| Error::CopyError {
+                    bucket: request.bucket,
+                    key: request.key,
+                    source: Box::new(source),
+                });
+        }
+
+        let (guard, upload_id) =
+            MultipartUploadGuard::create(self, &request.bucket, &request.key, attributes).await?;
+
+        let result = match self.copy_parts(&request, &source, &upload_id).await {
+            Ok(parts) => {
+                self.complete_multipart_upload(&request.bucket, &request.key, &upload_id, parts)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+
+        if result.is_err() {
+            let _ = self
+                .abort_multipart_upload(&request.bucket, &request.key, &upload_id)
+                .await;
+        }
+        guard.finish();
+
+        result
+    }
+
+    async fn delete_objects(&self, bucket: &str, keys: Vec<String>) -> Result<DeleteReport, Error> {
+        let mut report = DeleteReport::default();
+        for chunk in keys.chunks(MAX_DELETE_KEYS) {
+            let objects = chunk
+                .iter()
+                .map(|key| ObjectIdentifier::builder().key(key).build())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| Error::Other(e.to_string()))?;
+            let delete = Delete::builder()
+                .set_objects(Some(objects))
+                .build()
+                .map_err(|e| Error::Other(e.to_string()))?;
+
+            let result = self
+                .client
+                .delete_objects()
+                .bucket(bucket)
+                .delete(delete)
+                .send()
+                .await
+                .map_err(|source| Error::DeleteObjectsError {
+                    bucket: bucket.to_string(),
+                    source: Box::new(source),
+                })?;
+
+            report.deleted.extend(
+                result
+                    .deleted
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|x| x.key),
+            );
+            report
+                .failures
+                .extend(
+                    result
+                        .errors
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|x| DeleteFailure {
+                            key: x.key.unwrap_or_default(),
+                            code: x.code,
+                            message: x.message,
+                        }),
+                );
+        }
+        Ok(report)
+    }
+
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ListObjectsPage, Error> {
+        let output = self
+            .client
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .set_continuation_token(continuation_token)
+            .send()
+            .await
+            .map_err(|source| Error::ListError {
+                bucket: bucket.to_string(),
+                prefix: prefix.to_string(),
+                source: Box::new(source),
+            })?;
+
+        Ok(ListObjectsPage {
+            keys: output
+                .contents
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|x| x.key)
+                .collect(),
+            next_continuation_token: output.next_continuation_token,
+        })
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        attributes: ObjectAttributes,
+    ) -> Result<String, Error> {
+        self.client
+            .create_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .set_content_type(attributes.content_type)
+            .set_metadata(non_empty(attributes.metadata))
+            .set_storage_class(attributes.storage_class.as_deref().map(StorageClass::from))
+            .set_server_side_encryption(
+                attributes
+                    .server_side_encryption
+                    .as_deref()
+                    .map(ServerSideEncryption::from),
+            )
+            .set_ssekms_key_id(attributes.sse_kms_key_id)
+            .send()
+            .await
+            .map_err(|source| Error::CreateMultipartUploadError {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                source: Box::new(source),
+            })?
+            .upload_id
+            .ok_or_else(|| Error::Other(String::from("No upload id returned.")))
+    }
+
+    async fn upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Bytes,
+    ) -> Result<UploadedPart, Error> {
+        let output = self
+            .client
+            .upload_part()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .map_err(|source| Error::UploadPartError {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                source: Box::new(source),
+            })?;
+
+        Ok(UploadedPart {
+            part_number,
+            etag: output.e_tag.unwrap_or_default(),
+        })
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<UploadedPart>,
+    ) -> Result<(), Error> {
+        let parts = parts
+            .into_iter()
+            .map(|x| {
+                CompletedPart::builder()
+                    .part_number(x.part_number)
+                    .e_tag(x.etag)
+                    .build()
+            })
+            .collect();
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|source| Error::CompleteMultipartUploadError {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                source: Box::new(source),
+            })
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<(), Error> {
+        self.client
+            .abort_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|source| Error::AbortMultipartUploadError {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                source: Box::new(source),
+            })
+    }
+
+    async fn presign_get_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        expires_in: Duration,
+        options: PresignedUrlOptions,
+    ) -> Result<String, Error> {
+        let result = self
+            .client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .set_response_content_disposition(options.response_content_disposition)
+            .set_response_content_type(options.response_content_type)
+            .presigned(
+                PresigningConfig::expires_in(expires_in).map_err(Error::PresigningConfigError)?,
+            )
+            .await
+            .map_err(|source| Error::GetError {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                source: Box::new(source),
+            })?;
+        Ok(result.uri().to_string())
+    }
+
+    async fn presign_put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        content_type: Option<String>,
+        expires_in: Duration,
+    ) -> Result<String, Error> {
+        let result = self
+            .client
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .set_content_type(content_type)
+            .presigned(
+                PresigningConfig::expires_in(expires_in).map_err(Error::PresigningConfigError)?,
+            )
+            .await
+            .map_err(|source| Error::PutError {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                source: Box::new(source),
+            })?;
+        Ok(result.uri().to_string())
+    }
+
+    async fn presign_post_object(
+        &self,
+        bucket: &str,
+        policy: PostPolicy,
+    ) -> Result<PresignedPost, Error> {
+        let Some(credentials_provider) = &self.credentials_provider else {
+            return Err(Error::MissingCredentialsProvider);
+        };
+        let credentials = credentials_provider
+            .provide_credentials()
+            .await
+            .map_err(|e| Error::Other(format!("Error while loading credentials: {e}")))?;
+        let region = self
+            .client
+            .config()
+            .region()
+            .ok_or_else(|| Error::Other(String::from("No region configured.")))?
+            .to_string();
+
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_err(|e| Error::Other(e.to_string()))?
+            .as_secs();
+        let signing_time = SystemTime::UNIX_EPOCH + Duration::from_secs(now);
+        let expiration = format_date_time(now + policy.expires_in.as_secs())?;
+        // `20240101T000000Z`, derived from `2024-01-01T00:00:00Z`.
+        let amz_date = format_date_time(now)?.replace(['-', ':'], "");
+        let credential = format!(
+            "{}/{}/{region}/s3/aws4_request",
+            credentials.access_key_id(),
+            &amz_date[..8]
+        );
+
+        let mut fields = policy.fields();
+        fields.insert(
+            String::from("x-amz-algorithm"),
+            String::from("AWS4-HMAC-SHA256"),
+        );
+        fields.insert(String::from("x-amz-credential"), credential);
+        fields.insert(String::from("x-amz-date"), amz_date);
+        if let Some(token) = credentials.session_token() {
+            fields.insert(String::from("x-amz-security-token"), token.to_string());
+        }
+
+        let mut conditions = policy.conditions(bucket);
+        conditions.extend(fields.iter().map(|(name, value)| json!({ name: value })));
+        let document = json!({ "expiration": expiration, "conditions": conditions });
+        let encoded_policy = BASE64_STANDARD.encode(document.to_string());
+
+        let signing_key = sign::v4::generate_signing_key(
+            credentials.secret_access_key(),
+            signing_time,
+            &region,
+            "s3",
+        );
+        let signature = sign::v4::calculate_signature(signing_key, encoded_policy.as_bytes());
+        fields.insert(String::from("policy"), encoded_policy);
+        fields.insert(String::from("x-amz-signature"), signature);
+
+        Ok(PresignedPost {
+            url: self.bucket_url(bucket).await?,
+            fields,
+        })
+    }
+
+    fn client(&self) -> Option<&Client> {
+        Some(&self.client)
+    }
+}
+
+/// Aborts a multipart upload when dropped before `finish`, i.e. when the future copying into it
+/// is cancelled. Otherwise its parts would be kept, and billed, until a lifecycle rule removes
+/// them. The upload is created on its own task, so that an upload created after the cancellation
+/// is aborted as well.
+struct MultipartUploadGuard {
+    store: AwsObjectStore,
+    bucket: String,
+    key: String,
+    state: UploadState,
+}
+
+enum UploadState {
+    Creating(JoinHandle<Result<String, Error>>),
+    Created(String),
+    Finished,
+}
+
+impl MultipartUploadGuard {
+    async fn create(
+        store: &AwsObjectStore,
+        bucket: &str,
+        key: &str,
+        attributes: ObjectAttributes,
+    ) -> Result<(Self, String), Error> {
+        let task = {
+            let (store, bucket, key) = (store.clone(), bucket.to_string(), key.to_string());
+            tokio::spawn(async move {
+                store
+                    .create_multipart_upload(&bucket, &key, attributes)
+                    .await
+            })
+        };
+        let mut guard = Self {
+            store: store.clone(),
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            state: UploadState::Creating(task),
+        };
+
+        let UploadState::Creating(task) = &mut guard.state else {
+            unreachable!("the upload is being created");
+        };
+        let result = task.await;
+        guard.state = UploadState::Finished;
+        let upload_id = result.map_err(|e| Error::Other(e.to_string()))??;
+        guard.state = UploadState::Created(upload_id.clone());
+        Ok((guard, upload_id))
+    }
+
+    fn finish(mut self) {
+        self.state = UploadState::Finished;
+    }
+}
+
+impl Drop for MultipartUploadGuard {
+    fn drop(&mut self) {
+        let state = std::mem::replace(&mut self.state, UploadState::Finished);
+        if matches!(state, UploadState::Finished) {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let store = self.store.clone();
+        let bucket = std::mem::take(&mut self.bucket);
+        let key = std::mem::take(&mut self.key);
+        runtime.spawn(async move {
+            let upload_id = match state {
+                UploadState::Creating(task) => match task.await {
+                    Ok(Ok(upload_id)) => upload_id,
+                    _ => return,
+                },
+                UploadState::Created(upload_id) => upload_id,
+                UploadState::Finished => return,
+            };
+            let _ = store
+                .abort_multipart_upload(&bucket, &key, &upload_id)
+                .await;
+        });
+    }
+}
+
+/// Value of the `x-amz-copy-source` header: the bucket and the url-encoded key.
+fn copy_source(bucket: &str, key: &str) -> String {
+    let mut source = format!("{bucket}/");
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                source.push(byte as char)
+            }
+            _ => source.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    source
+}
+
+fn format_date_time(secs: u64) -> Result<String, Error> {
+    DateTime::from_secs(secs as i64)
+        .fmt(DateTimeFormat::DateTime)
+        .map_err(|e| Error::Other(e.to_string()))
+}
+
+fn non_empty(metadata: HashMap<String, String>) -> Option<HashMap<String, String>> {
+    (!metadata.is_empty()).then_some(metadata)
+}
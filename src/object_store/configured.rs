@@ -0,0 +1,325 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use aws_sdk_s3::Client;
+use bytes::Bytes;
+
+use crate::{
+    delete_report::{DeleteFailure, DeleteReport},
+    error::{Error, KeyBuildError},
+    post_policy::{KeyCondition, PostPolicy, PresignedPost},
+    presigned_url_options::PresignedUrlOptions,
+    s3_object_meta::S3ObjectMeta,
+    traits::key_builder::MAX_KEY_LENGTH,
+};
+
+use super::{
+    CopyObjectRequest, GetObjectRequest, GetObjectResponse, ListObjectsPage, ObjectAttributes,
+    ObjectStore, PutObjectRequest, PutObjectResponse, UploadedPart,
+};
+
+/// Wraps another store, applying the settings of `S3ContextBuilder`: keys are namespaced under
+/// `key_prefix`, bucket names are remapped, uploads get the default attributes and every request
+/// is bounded by `timeout`. Keys handed back to the caller have the prefix removed.
+///
+/// The timeout covers the call into the wrapped store, i.e. until a get returns its response,
+/// but not reading the returned body. Copies and batch deletes may send many requests and aren't
+/// bounded as a whole; use the timeouts of the SDK client to bound each of their requests.
+#[derive(Debug, Clone)]
+pub struct ConfiguredObjectStore {
+    pub inner: Arc<dyn ObjectStore>,
+    pub key_prefix: String,
+    /// Actual bucket names by the names used in the code.
+    pub bucket_names: HashMap<String, String>,
+    /// Storage class and encryption settings used when an upload doesn't set its own.
+    pub default_attributes: ObjectAttributes,
+    pub timeout: Option<Duration>,
+}
+
+impl ConfiguredObjectStore {
+    pub fn new(inner: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            inner,
+            key_prefix: String::new(),
+            bucket_names: HashMap::new(),
+            default_attributes: ObjectAttributes::default(),
+            timeout: None,
+        }
+    }
+
+    fn bucket(&self, bucket: &str) -> String {
+        self.bucket_names
+            .get(bucket)
+            .cloned()
+            .unwrap_or_else(|| bucket.to_string())
+    }
+
+    /// The stored key of `key`, which like any key S3 accepts is at most `MAX_KEY_LENGTH` bytes
+    /// long once prefixed.
+    fn key(&self, key: &str) -> Result<String, Error> {
+        let key = self.prefix(key);
+        if key.len() > MAX_KEY_LENGTH {
+            return Err(Error::KeyBuildError(KeyBuildError::TooLong {
+                length: key.len(),
+                key,
+                max_length: MAX_KEY_LENGTH,
+            }));
+        }
+        Ok(key)
+    }
+
+    fn prefix(&self, prefix: &str) -> String {
+        format!("{}{prefix}", self.key_prefix)
+    }
+
+    fn strip_key(&self, key: String) -> String {
+        match key.strip_prefix(&self.key_prefix) {
+            Some(x) => x.to_string(),
+            None => key,
+        }
+    }
+
+    fn strip_meta(&self, mut meta: S3ObjectMeta) -> S3ObjectMeta {
+        meta.key = self.strip_key(meta.key);
+        meta
+    }
+
+    async fn with_timeout<T>(
+        &self,
+        operation: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, operation)
+                .await
+                .map_err(|_| Error::Timeout(timeout))?,
+            None => operation.await,
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for ConfiguredObjectStore {
+    async fn put_object(&self, request: PutObjectRequest) -> Result<PutObjectResponse, Error> {
+        let request = PutObjectRequest {
+            bucket: self.bucket(&request.bucket),
+            key: self.key(&request.key)?,
+            attributes: request.attributes.or_defaults(&self.default_attributes),
+            ..request
+        };
+        self.with_timeout(self.inner.put_object(request)).await
+    }
+
+    async fn get_object(
+        &self,
+        request: GetObjectRequest,
+    ) -> Result<Option<GetObjectResponse>, Error> {
+        let request = GetObjectRequest {
+            bucket: self.bucket(&request.bucket),
+            key: self.key(&request.key)?,
+            ..request
+        };
+        let response = self.with_timeout(self.inner.get_object(request)).await?;
+        Ok(response.map(|x| GetObjectResponse {
+            meta: self.strip_meta(x.meta),
+            ..x
+        }))
+    }
+
+    async fn head_object(&self, bucket: &str, key: &str) -> Result<Option<S3ObjectMeta>, Error> {
+        let meta = self
+            .with_timeout(
+                self.inner
+                    .head_object(&self.bucket(bucket), &self.key(key)?),
+            )
+            .await?;
+        Ok(meta.map(|x| self.strip_meta(x)))
+    }
+
+    async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), Error> {
+        self.with_timeout(
+            self.inner
+                .delete_object(&self.bucket(bucket), &self.key(key)?),
+        )
+        .await
+    }
+
+    async fn copy_object(&self, request: CopyObjectRequest) -> Result<(), Error> {
+        let request = CopyObjectRequest {
+            source_bucket: self.bucket(&request.source_bucket),
+            source_key: self.key(&request.source_key)?,
+            bucket: self.bucket(&request.bucket),
+            key: self.key(&request.key)?,
+            attributes: request
+                .attributes
+                .map(|x| x.or_defaults(&self.default_attributes)),
+            default_attributes: request
+                .default_attributes
+                .or_defaults(&self.default_attributes),
+        };
+        self.inner.copy_object(request).await
+    }
+
+    async fn delete_objects(&self, bucket: &str, keys: Vec<String>) -> Result<DeleteReport, Error> {
+        let keys = keys.iter().map(|x| self.key(x)).collect::<Result<_, _>>()?;
+        let report = self
+            .inner
+            .delete_objects(&self.bucket(bucket), keys)
+            .await?;
+        Ok(DeleteReport {
+            matched: report
+                .matched
+                .into_iter()
+                .map(|x| self.strip_key(x))
+                .collect(),
+            deleted: report
+                .deleted
+                .into_iter()
+                .map(|x| self.strip_key(x))
+                .collect(),
+            failures: report
+                .failures
+                .into_iter()
+                .map(|x| DeleteFailure {
+                    key: self.strip_key(x.key),
+                    ..x
+                })
+                .collect(),
+        })
+    }
+
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ListObjectsPage, Error> {
+        let page = self
+            .with_timeout(self.inner.list_objects(
+                &self.bucket(bucket),
+                &self.prefix(prefix),
+                continuation_token,
+            ))
+            .await?;
+        Ok(ListObjectsPage {
+            keys: page.keys.into_iter().map(|x| self.strip_key(x)).collect(),
+            ..page
+        })
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        attributes: ObjectAttributes,
+    ) -> Result<String, Error> {
+        self.with_timeout(self.inner.create_multipart_upload(
+            &self.bucket(bucket),
+            &self.key(key)?,
+            attributes.or_defaults(&self.default_attributes),
+        ))
+        .await
+    }
+
+    async fn upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Bytes,
+    ) -> Result<UploadedPart, Error> {
+        self.with_timeout(self.inner.upload_part(
+            &self.bucket(bucket),
+            &self.key(key)?,
+            upload_id,
+            part_number,
+            body,
+        ))
+        .await
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<UploadedPart>,
+    ) -> Result<(), Error> {
+        self.with_timeout(self.inner.complete_multipart_upload(
+            &self.bucket(bucket),
+            &self.key(key)?,
+            upload_id,
+            parts,
+        ))
+        .await
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<(), Error> {
+        self.with_timeout(self.inner.abort_multipart_upload(
+            &self.bucket(bucket),
+            &self.key(key)?,
+            upload_id,
+        ))
+        .await
+    }
+
+    async fn presign_get_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        expires_in: Duration,
+        options: PresignedUrlOptions,
+    ) -> Result<String, Error> {
+        self.inner
+            .presign_get_object(&self.bucket(bucket), &self.key(key)?, expires_in, options)
+            .await
+    }
+
+    async fn presign_put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        content_type: Option<String>,
+        expires_in: Duration,
+    ) -> Result<String, Error> {
+        self.inner
+            .presign_put_object(
+                &self.bucket(bucket),
+                &self.key(key)?,
+                content_type,
+                expires_in,
+            )
+            .await
+    }
+
+    async fn presign_post_object(
+        &self,
+        bucket: &str,
+        policy: PostPolicy,
+    ) -> Result<PresignedPost, Error> {
+        // Without a key condition the upload still has to stay under the prefix.
+        let key = match policy.key {
+            Some(KeyCondition::Exact(key)) => Some(KeyCondition::Exact(self.key(&key)?)),
+            Some(KeyCondition::StartsWith(prefix)) => {
+                Some(KeyCondition::StartsWith(self.prefix(&prefix)))
+            }
+            None if !self.key_prefix.is_empty() => {
+                Some(KeyCondition::StartsWith(self.key_prefix.clone()))
+            }
+            None => None,
+        };
+        let policy = PostPolicy { key, ..policy };
+        self.inner
+            .presign_post_object(&self.bucket(bucket), policy)
+            .await
+    }
+
+    fn client(&self) -> Option<&Client> {
+        self.inner.client()
+    }
+}
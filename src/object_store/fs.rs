@@ -0,0 +1,608 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::{ByteStream, DateTime, Length};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+    sync::Mutex,
+};
+
+use crate::{error::Error, key_format::sha256, s3_object_meta::S3ObjectMeta};
+
+use super::{
+    GetObjectRequest, GetObjectResponse, ListObjectsPage, MultipartEtag, ObjectAttributes,
+    ObjectStore, PutObjectRequest, PutObjectResponse, UploadedPart, check_preconditions, md5_etag,
+    missing_part, no_such_upload, range_not_satisfiable, resolve_range,
+};
+
+const LIST_PAGE_SIZE: usize = 1000;
+
+/// Directory under the root holding sidecar metadata, temp files and pending uploads. S3 bucket
+/// names cannot start with a dot, so it never clashes with a bucket.
+const INTERNAL_DIR: &str = ".s3-bucket";
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Object store keeping objects as files under `root`, at `<root>/<bucket>/<key>`.
+///
+/// Content type, user metadata and the ETag are kept in JSON sidecar files at
+/// `<root>/.s3-bucket/meta/<bucket>/<sha256 of the key>.json`, outside the object tree, so every
+/// path below a bucket is a key.
+/// Writes go to a temp file which is then renamed into place.
+///
+/// As a file can't also be a directory, a key can't be stored when it is a prefix of another key
+/// followed by `/`: with `a/b` stored, putting `a` fails with `Error::InvalidKey`, and vice versa.
+#[derive(Debug)]
+pub struct FsObjectStore {
+    root: PathBuf,
+    /// Serializes writes so conditional puts check and replace an object atomically. Reads hold it
+    /// while opening an object, so the body always matches its sidecar.
+    lock: Mutex<()>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Sidecar {
+    etag: String,
+    attributes: ObjectAttributes,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingUpload {
+    bucket: String,
+    key: String,
+    attributes: ObjectAttributes,
+}
+
+impl FsObjectStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn bucket_path(&self, bucket: &str) -> Result<PathBuf, Error> {
+        if bucket.is_empty() || bucket.starts_with('.') || bucket.contains(['/', '\\']) {
+            return Err(Error::Other(format!("Invalid bucket name `{bucket}`.")));
+        }
+        Ok(self.root.join(bucket))
+    }
+
+    fn object_path(&self, bucket: &str, key: &str) -> Result<PathBuf, Error> {
+        Ok(self.bucket_path(bucket)?.join(relative_path(key)?))
+    }
+
+    /// Sidecars are named after the hash of their key, as any name derived from the key itself
+    /// could be the path of another key, e.g. `a.json` of `a` and of `a.json/b`.
+    fn sidecar_path(&self, bucket: &str, key: &str) -> Result<PathBuf, Error> {
+        self.object_path(bucket, key)?;
+        Ok(self
+            .internal_path("meta")
+            .join(bucket)
+            .join(format!("{}.json", sha256(key))))
+    }
+
+    fn internal_path(&self, name: &str) -> PathBuf {
+        self.root.join(INTERNAL_DIR).join(name)
+    }
+
+    fn upload_path(&self, upload_id: &str) -> Result<PathBuf, Error> {
+        if upload_id.is_empty()
+            || !upload_id
+                .chars()
+                .all(|x| x.is_ascii_alphanumeric() || x == '-')
+        {
+            return Err(no_such_upload(upload_id));
+        }
+        Ok(self.internal_path("uploads").join(upload_id))
+    }
+
+    /// Writes `bytes` to `path` through a temp file, so readers never see a partial file.
+    async fn write_atomic(&self, path: &Path, bytes: &[u8]) -> Result<(), Error> {
+        let temp_path = self.temp_path().await?;
+        let result = async {
+            tokio::fs::write(&temp_path, bytes).await?;
+            self.rename_into_place(&temp_path, path).await
+        }
+        .await;
+
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+        }
+        result.map_err(Error::IoError)
+    }
+
+    async fn rename_into_place(&self, temp_path: &Path, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(temp_path, path).await
+    }
+
+    async fn temp_path(&self) -> Result<PathBuf, Error> {
+        let dir = self.internal_path("tmp");
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(Error::IoError)?;
+        Ok(dir.join(unique_id()))
+    }
+
+    async fn read_sidecar(&self, bucket: &str, key: &str) -> Result<Option<Sidecar>, Error> {
+        let path = self.sidecar_path(bucket, key)?;
+        let Some(bytes) = not_found_as_none(tokio::fs::read(&path).await)? else {
+            return Ok(None);
+        };
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| Error::Other(format!("Invalid metadata file for key `{key}`: {e}")))
+    }
+
+    async fn write_sidecar(&self, bucket: &str, key: &str, sidecar: &Sidecar) -> Result<(), Error> {
+        let bytes = serde_json::to_vec(sidecar).map_err(|e| Error::Other(e.to_string()))?;
+        self.write_atomic(&self.sidecar_path(bucket, key)?, &bytes)
+            .await
+    }
+
+    /// Writes the sidecar of an object whose body was just written. If that fails the previous
+    /// sidecar is removed, so the object falls back to an ETag computed from its new content
+    /// rather than keeping the ETag and attributes of the body it replaced.
+    async fn write_sidecar_or_remove(
+        &self,
+        bucket: &str,
+        key: &str,
+        sidecar: &Sidecar,
+    ) -> Result<(), Error> {
+        let result = self.write_sidecar(bucket, key, sidecar).await;
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(self.sidecar_path(bucket, key)?).await;
+        }
+        result
+    }
+
+    /// Opens an existing object along with its sidecar. Files placed in the tree by other means
+    /// get their ETag computed from the content. Callers must hold `lock`.
+    async fn open_object(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<Option<(File, std::fs::Metadata, Sidecar)>, Error> {
+        let path = self.object_path(bucket, key)?;
+        let Some(mut file) = not_found_as_none(File::open(&path).await)? else {
+            return Ok(None);
+        };
+        let metadata = file.metadata().await.map_err(Error::IoError)?;
+        if !metadata.is_file() {
+            return Ok(None);
+        }
+
+        let sidecar = match self.read_sidecar(bucket, key).await? {
+            Some(x) => x,
+            None => {
+                let mut bytes = vec![];
+                file.read_to_end(&mut bytes).await.map_err(Error::IoError)?;
+                file.rewind().await.map_err(Error::IoError)?;
+                Sidecar {
+                    etag: md5_etag(&bytes),
+                    attributes: ObjectAttributes::default(),
+                }
+            }
+        };
+        Ok(Some((file, metadata, sidecar)))
+    }
+
+    /// Fails if `key` can't be stored as a file because an object is stored at one of its parent
+    /// directories, or keys below it are stored in a directory at its path.
+    async fn check_path_conflicts(&self, bucket: &str, key: &str) -> Result<(), Error> {
+        let bucket_path = self.bucket_path(bucket)?;
+        let invalid = |reason: String| Error::InvalidKey {
+            key: key.to_string(),
+            reason,
+        };
+
+        let mut path = bucket_path.clone();
+        let segments: Vec<&str> = key.split('/').collect();
+        for (index, segment) in segments.iter().enumerate() {
+            path.push(segment);
+            let Some(metadata) = not_found_as_none(tokio::fs::metadata(&path).await)? else {
+                return Ok(());
+            };
+            let is_last = index + 1 == segments.len();
+            if !is_last && !metadata.is_dir() {
+                return Err(invalid(format!(
+                    "the object `{}` exists, so no key below it can be stored",
+                    segments[..=index].join("/")
+                )));
+            }
+            if is_last && metadata.is_dir() {
+                return Err(invalid(String::from(
+                    "keys below it exist, so it can't be stored as an object",
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes empty directories from `path` upwards, stopping at `stop`.
+    async fn prune_empty_dirs(&self, path: &Path, stop: &Path) {
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            if current == stop || !current.starts_with(stop) {
+                break;
+            }
+            if tokio::fs::remove_dir(current).await.is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for FsObjectStore {
+    async fn put_object(&self, request: PutObjectRequest) -> Result<PutObjectResponse, Error> {
+        let path = self.object_path(&request.bucket, &request.key)?;
+        let _guard = self.lock.lock().await;
+
+        check_preconditions(
+            &request,
+            self.open_object(&request.bucket, &request.key)
+                .await?
+                .map(|(_, _, x)| x.etag)
+                .as_deref(),
+        )?;
+
+        self.check_path_conflicts(&request.bucket, &request.key)
+            .await?;
+
+        let sidecar = Sidecar {
+            etag: md5_etag(&request.body),
+            attributes: request.attributes,
+        };
+        self.write_atomic(&path, &request.body).await?;
+        self.write_sidecar_or_remove(&request.bucket, &request.key, &sidecar)
+            .await?;
+
+        Ok(PutObjectResponse {
+            etag: Some(sidecar.etag),
+        })
+    }
+
+    async fn get_object(
+        &self,
+        request: GetObjectRequest,
+    ) -> Result<Option<GetObjectResponse>, Error> {
+        let opened = {
+            let _guard = self.lock.lock().await;
+            self.open_object(&request.bucket, &request.key).await?
+        };
+        let Some((file, metadata, sidecar)) = opened else {
+            return Ok(None);
+        };
+
+        if request.if_match.is_some_and(|x| x != sidecar.etag) {
+            return Err(Error::PreconditionFailed {
+                bucket: request.bucket,
+                key: request.key,
+            });
+        }
+
+        let total_size = metadata.len();
+        let (start, end) = match request.range {
+            Some(range) => match resolve_range(range, total_size) {
+                Some(x) => x,
+                None => return Err(range_not_satisfiable(range, &request.key)),
+            },
+            None if total_size == 0 => {
+                return Ok(Some(GetObjectResponse {
+                    body: ByteStream::from(Bytes::new()),
+                    meta: object_meta(&request.key, 0, sidecar, &metadata),
+                    content_range: None,
+                }));
+            }
+            None => (0, total_size - 1),
+        };
+
+        let body = ByteStream::read_from()
+            .file(file)
+            .offset(start)
+            .length(Length::Exact(end - start + 1))
+            .build()
+            .await
+            .map_err(|e| Error::Other(e.to_string()))?;
+
+        Ok(Some(GetObjectResponse {
+            body,
+            meta: object_meta(&request.key, end - start + 1, sidecar, &metadata),
+            content_range: request
+                .range
+                .map(|_| format!("bytes {start}-{end}/{total_size}")),
+        }))
+    }
+
+    async fn head_object(&self, bucket: &str, key: &str) -> Result<Option<S3ObjectMeta>, Error> {
+        let _guard = self.lock.lock().await;
+        Ok(self
+            .open_object(bucket, key)
+            .await?
+            .map(|(_, metadata, sidecar)| object_meta(key, metadata.len(), sidecar, &metadata)))
+    }
+
+    async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), Error> {
+        let path = self.object_path(bucket, key)?;
+        let sidecar_path = self.sidecar_path(bucket, key)?;
+        let _guard = self.lock.lock().await;
+
+        not_found_as_none(tokio::fs::remove_file(&path).await)?;
+        not_found_as_none(tokio::fs::remove_file(&sidecar_path).await)?;
+        self.prune_empty_dirs(&path, &self.bucket_path(bucket)?)
+            .await;
+        Ok(())
+    }
+
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ListObjectsPage, Error> {
+        let bucket_path = self.bucket_path(bucket)?;
+
+        // Only the directory holding the prefix has to be walked.
+        let prefix_dir = prefix.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+        let start = match prefix_dir {
+            "" => ListEntry::dir(String::new(), bucket_path),
+            dir => ListEntry::dir(format!("{dir}/"), bucket_path.join(relative_path(dir)?)),
+        };
+
+        // Directories are walked depth first with their entries sorted, so keys come out in
+        // order and the walk can stop as soon as the page is full. Directories whose keys all
+        // fall outside the prefix or before the continuation token are skipped.
+        let token = continuation_token.as_deref();
+        let mut page = vec![];
+        let mut next_continuation_token = None;
+        let mut pending = vec![start];
+        while let Some(entry) = pending.pop() {
+            if !entry.is_dir {
+                if !entry.key.starts_with(prefix) || token.is_some_and(|x| entry.key.as_str() <= x)
+                {
+                    continue;
+                }
+                if page.len() == LIST_PAGE_SIZE {
+                    next_continuation_token = page.last().cloned();
+                    break;
+                }
+                page.push(entry.key);
+                continue;
+            }
+
+            let in_prefix = entry.key.starts_with(prefix) || prefix.starts_with(&entry.key);
+            let before_token =
+                token.is_some_and(|x| x > entry.key.as_str() && !x.starts_with(&entry.key));
+            if !in_prefix || before_token {
+                continue;
+            }
+
+            let Some(mut read_dir) = not_found_as_none(tokio::fs::read_dir(&entry.path).await)?
+            else {
+                continue;
+            };
+            let mut children = vec![];
+            while let Some(child) = read_dir.next_entry().await.map_err(Error::IoError)? {
+                let Ok(name) = child.file_name().into_string() else {
+                    continue;
+                };
+                let is_dir = child.file_type().await.map_err(Error::IoError)?.is_dir();
+                // A directory sorts as its name followed by `/`, the way its keys do.
+                let key = match is_dir {
+                    true => format!("{}{name}/", entry.key),
+                    false => format!("{}{name}", entry.key),
+                };
+                children.push(ListEntry {
+                    key,
+                    path: child.path(),
+                    is_dir,
+                });
+            }
+            children.sort_by(|a, b| b.key.cmp(&a.key));
+            pending.extend(children);
+        }
+
+        Ok(ListObjectsPage {
+            keys: page,
+            next_continuation_token,
+        })
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        attributes: ObjectAttributes,
+    ) -> Result<String, Error> {
+        self.object_path(bucket, key)?;
+        let upload_id = unique_id();
+        let upload = PendingUpload {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            attributes,
+        };
+        let bytes = serde_json::to_vec(&upload).map_err(|e| Error::Other(e.to_string()))?;
+        self.write_atomic(&self.upload_path(&upload_id)?.join("upload.json"), &bytes)
+            .await?;
+        Ok(upload_id)
+    }
+
+    async fn upload_part(
+        &self,
+        _bucket: &str,
+        _key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Bytes,
+    ) -> Result<UploadedPart, Error> {
+        let upload_path = self.upload_path(upload_id)?;
+        if !tokio::fs::try_exists(&upload_path)
+            .await
+            .map_err(Error::IoError)?
+        {
+            return Err(no_such_upload(upload_id));
+        }
+
+        self.write_atomic(&upload_path.join(part_number.to_string()), &body)
+            .await?;
+        Ok(UploadedPart {
+            part_number,
+            etag: md5_etag(&body),
+        })
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        _bucket: &str,
+        _key: &str,
+        upload_id: &str,
+        parts: Vec<UploadedPart>,
+    ) -> Result<(), Error> {
+        let upload_path = self.upload_path(upload_id)?;
+        let Some(bytes) =
+            not_found_as_none(tokio::fs::read(upload_path.join("upload.json")).await)?
+        else {
+            return Err(no_such_upload(upload_id));
+        };
+        let upload: PendingUpload =
+            serde_json::from_slice(&bytes).map_err(|e| Error::Other(e.to_string()))?;
+
+        let temp_path = self.temp_path().await?;
+        let result = async {
+            let mut file = File::create(&temp_path).await.map_err(Error::IoError)?;
+            let mut etag = MultipartEtag::default();
+            for part in &parts {
+                let path = upload_path.join(part.part_number.to_string());
+                let Some(bytes) = not_found_as_none(tokio::fs::read(path).await)? else {
+                    return Err(missing_part(part.part_number));
+                };
+                file.write_all(&bytes).await.map_err(Error::IoError)?;
+                etag.push(&bytes);
+            }
+            file.flush().await.map_err(Error::IoError)?;
+
+            let _guard = self.lock.lock().await;
+            self.check_path_conflicts(&upload.bucket, &upload.key)
+                .await?;
+            self.rename_into_place(&temp_path, &self.object_path(&upload.bucket, &upload.key)?)
+                .await
+                .map_err(Error::IoError)?;
+            let sidecar = Sidecar {
+                etag: etag.finish(),
+                attributes: upload.attributes,
+            };
+            self.write_sidecar_or_remove(&upload.bucket, &upload.key, &sidecar)
+                .await
+        }
+        .await;
+
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return result;
+        }
+
+        let _ = tokio::fs::remove_dir_all(&upload_path).await;
+        Ok(())
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        _bucket: &str,
+        _key: &str,
+        upload_id: &str,
+    ) -> Result<(), Error> {
+        let upload_path = self.upload_path(upload_id)?;
+        not_found_as_none(tokio::fs::remove_dir_all(upload_path).await)?;
+        Ok(())
+    }
+}
+
+fn object_meta(
+    key: &str,
+    size: u64,
+    sidecar: Sidecar,
+    metadata: &std::fs::Metadata,
+) -> S3ObjectMeta {
+    S3ObjectMeta {
+        key: key.to_string(),
+        size,
+        etag: Some(sidecar.etag),
+        content_type: sidecar.attributes.content_type,
+        last_modified: metadata.modified().ok().map(DateTime::from),
+        metadata: sidecar.attributes.metadata,
+        storage_class: sidecar
+            .attributes
+            .storage_class
+            .or(Some(String::from("STANDARD"))),
+        version_id: None,
+    }
+}
+
+/// Maps a key to a relative path, rejecting keys which would escape the bucket directory or
+/// cannot be represented as a file.
+fn relative_path(key: &str) -> Result<PathBuf, Error> {
+    let invalid = |reason: &str| Error::InvalidKey {
+        key: key.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let mut path = PathBuf::new();
+    for segment in key.split('/') {
+        match segment {
+            "" => return Err(invalid("empty path segments cannot be stored as files")),
+            "." | ".." => return Err(invalid("`.` and `..` segments are not allowed")),
+            x if x.contains('\\') || x.contains('\0') => {
+                return Err(invalid("backslashes and nul characters are not allowed"));
+            }
+            x => path.push(x),
+        }
+    }
+    Ok(path)
+}
+
+/// A file or directory met while listing. The key of a directory ends with `/`.
+struct ListEntry {
+    key: String,
+    path: PathBuf,
+    is_dir: bool,
+}
+
+impl ListEntry {
+    fn dir(key: String, path: PathBuf) -> Self {
+        Self {
+            key,
+            path,
+            is_dir: true,
+        }
+    }
+}
+
+fn unique_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|x| x.as_nanos())
+        .unwrap_or_default();
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    format!("{}-{nanos}-{id}", std::process::id())
+}
+
+fn not_found_as_none<T>(result: std::io::Result<T>) -> Result<Option<T>, Error> {
+    match result {
+        Ok(x) => Ok(Some(x)),
+        Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::NotADirectory) => Ok(None),
+        Err(e) => Err(Error::IoError(e)),
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::SystemTime,
+};
+
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::{ByteStream, DateTime};
+use bytes::{Bytes, BytesMut};
+
+use crate::{error::Error, s3_object_meta::S3ObjectMeta};
+
+use super::{
+    GetObjectRequest, GetObjectResponse, ListObjectsPage, MultipartEtag, ObjectAttributes,
+    ObjectStore, PutObjectRequest, PutObjectResponse, UploadedPart, check_preconditions, md5_etag,
+    missing_part, no_such_upload, range_not_satisfiable, resolve_range,
+};
+
+const LIST_PAGE_SIZE: usize = 1000;
+
+/// Thread-safe object store kept in memory, meant for tests. Buckets are created on first write.
+#[derive(Debug, Default)]
+pub struct InMemoryObjectStore {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    buckets: HashMap<String, BTreeMap<String, StoredObject>>,
+    uploads: HashMap<String, PendingUpload>,
+    next_upload_id: u64,
+}
+
+#[derive(Debug, Clone)]
+struct StoredObject {
+    body: Bytes,
+    attributes: ObjectAttributes,
+    etag: String,
+    last_modified: DateTime,
+}
+
+#[derive(Debug)]
+struct PendingUpload {
+    bucket: String,
+    key: String,
+    attributes: ObjectAttributes,
+    parts: BTreeMap<i32, Bytes>,
+}
+
+impl InMemoryObjectStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl StoredObject {
+    fn new(body: Bytes, attributes: ObjectAttributes, etag: String) -> Self {
+        Self {
+            body,
+            attributes,
+            etag,
+            last_modified: DateTime::from(SystemTime::now()),
+        }
+    }
+
+    fn meta(&self, key: &str, size: u64) -> S3ObjectMeta {
+        S3ObjectMeta {
+            key: key.to_string(),
+            size,
+            etag: Some(self.etag.clone()),
+            content_type: self.attributes.content_type.clone(),
+            last_modified: Some(self.last_modified),
+            metadata: self.attributes.metadata.clone(),
+            storage_class: self
+                .attributes
+                .storage_class
+                .clone()
+                .or(Some(String::from("STANDARD"))),
+            version_id: None,
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for InMemoryObjectStore {
+    async fn put_object(&self, request: PutObjectRequest) -> Result<PutObjectResponse, Error> {
+        let mut state = self.state();
+        let bucket = state.buckets.entry(request.bucket.clone()).or_default();
+        check_preconditions(&request, bucket.get(&request.key).map(|x| x.etag.as_str()))?;
+
+        let etag = md5_etag(&request.body);
+        let object = StoredObject::new(request.body, request.attributes, etag.clone());
+        bucket.insert(request.key, object);
+
+        Ok(PutObjectResponse { etag: Some(etag) })
+    }
+
+    async fn get_object(
+        &self,
+        request: GetObjectRequest,
+    ) -> Result<Option<GetObjectResponse>, Error> {
+        let state = self.state();
+        let Some(object) = state
+            .buckets
+            .get(&request.bucket)
+            .and_then(|x| x.get(&request.key))
+        else {
+            return Ok(None);
+        };
+
+        if request.if_match.is_some_and(|x| x != object.etag) {
+            return Err(Error::PreconditionFailed {
+                bucket: request.bucket,
+                key: request.key,
+            });
+        }
+
+        let total_size = object.body.len() as u64;
+        let Some(range) = request.range else {
+            return Ok(Some(GetObjectResponse {
+                body: ByteStream::from(object.body.clone()),
+                meta: object.meta(&request.key, total_size),
+                content_range: None,
+            }));
+        };
+
+        let Some((start, end)) = resolve_range(range, total_size) else {
+            return Err(range_not_satisfiable(range, &request.key));
+        };
+
+        let body = object.body.slice(start as usize..=end as usize);
+        Ok(Some(GetObjectResponse {
+            meta: object.meta(&request.key, body.len() as u64),
+            body: ByteStream::from(body),
+            content_range: Some(format!("bytes {start}-{end}/{total_size}")),
+        }))
+    }
+
+    async fn head_object(&self, bucket: &str, key: &str) -> Result<Option<S3ObjectMeta>, Error> {
+        let state = self.state();
+        Ok(state
+            .buckets
+            .get(bucket)
+            .and_then(|x| x.get(key))
+            .map(|x| x.meta(key, x.body.len() as u64)))
+    }
+
+    async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), Error> {
+        if let Some(bucket) = self.state().buckets.get_mut(bucket) {
+            bucket.remove(key);
+        }
+        Ok(())
+    }
+
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ListObjectsPage, Error> {
+        let state = self.state();
+        let Some(bucket) = state.buckets.get(bucket) else {
+            return Ok(ListObjectsPage::default());
+        };
+
+        let mut keys = bucket
+            .keys()
+            .skip_while(|x| continuation_token.as_ref().is_some_and(|token| *x <= token))
+            .filter(|x| x.starts_with(prefix));
+
+        let page: Vec<String> = keys.by_ref().take(LIST_PAGE_SIZE).cloned().collect();
+        let next_continuation_token = match keys.next() {
+            Some(_) => page.last().cloned(),
+            None => None,
+        };
+
+        Ok(ListObjectsPage {
+            keys: page,
+            next_continuation_token,
+        })
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        attributes: ObjectAttributes,
+    ) -> Result<String, Error> {
+        let mut state = self.state();
+        state.next_upload_id += 1;
+        let upload_id = format!("upload-{}", state.next_upload_id);
+        state.uploads.insert(
+            upload_id.clone(),
+            PendingUpload {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                attributes,
+                parts: BTreeMap::new(),
+            },
+        );
+        Ok(upload_id)
+    }
+
+    async fn upload_part(
+        &self,
+        _bucket: &str,
+        _key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Bytes,
+    ) -> Result<UploadedPart, Error> {
+        let mut state = self.state();
+        let Some(upload) = state.uploads.get_mut(upload_id) else {
+            return Err(no_such_upload(upload_id));
+        };
+
+        let etag = md5_etag(&body);
+        upload.parts.insert(part_number, body);
+        Ok(UploadedPart { part_number, etag })
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        _bucket: &str,
+        _key: &str,
+        upload_id: &str,
+        parts: Vec<UploadedPart>,
+    ) -> Result<(), Error> {
+        let mut state = self.state();
+        let Some(upload) = state.uploads.get(upload_id) else {
+            return Err(no_such_upload(upload_id));
+        };
+
+        // Like on S3, the upload stays in place when completing it fails.
+        let mut body = BytesMut::new();
+        let mut etag = MultipartEtag::default();
+        for part in &parts {
+            let Some(bytes) = upload.parts.get(&part.part_number) else {
+                return Err(missing_part(part.part_number));
+            };
+            body.extend_from_slice(bytes);
+            etag.push(bytes);
+        }
+
+        let Some(upload) = state.uploads.remove(upload_id) else {
+            return Err(no_such_upload(upload_id));
+        };
+        let etag = etag.finish();
+        let object = StoredObject::new(body.freeze(), upload.attributes, etag);
+        state
+            .buckets
+            .entry(upload.bucket)
+            .or_default()
+            .insert(upload.key, object);
+        Ok(())
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        _bucket: &str,
+        _key: &str,
+        upload_id: &str,
+    ) -> Result<(), Error> {
+        self.state().uploads.remove(upload_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+use aws_sdk_s3::{Client, primitives::ByteStream};
+use bytes::Bytes;
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    byte_range::ByteRange,
+    delete_report::{DeleteFailure, DeleteReport},
+    error::Error,
+    post_policy::{PostPolicy, PresignedPost},
+    presigned_url_options::PresignedUrlOptions,
+    s3_object_meta::S3ObjectMeta,
+};
+
+pub mod aws;
+pub mod configured;
+pub mod fs;
+pub mod in_memory;
+pub mod retrying;
+
+pub use aws::AwsObjectStore;
+pub use configured::ConfiguredObjectStore;
+pub use fs::FsObjectStore;
+pub use in_memory::InMemoryObjectStore;
+pub use retrying::RetryingObjectStore;
+
+#[cfg(test)]
+mod tests;
+
+/// Storage backend used by `S3Context`. Missing objects are reported as `None` rather than as an
+/// error, so every backend shares the same not-found semantics.
+#[async_trait]
+pub trait ObjectStore: std::fmt::Debug + Send + Sync {
+    async fn put_object(&self, request: PutObjectRequest) -> Result<PutObjectResponse, Error>;
+
+    async fn get_object(
+        &self,
+        request: GetObjectRequest,
+    ) -> Result<Option<GetObjectResponse>, Error>;
+
+    async fn head_object(&self, bucket: &str, key: &str) -> Result<Option<S3ObjectMeta>, Error>;
+
+    async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), Error>;
+
+    /// Copies an object, possibly across buckets. The default downloads the source and uploads it
+    /// again; backends able to copy server side should override it.
+    async fn copy_object(&self, request: CopyObjectRequest) -> Result<(), Error> {
+        let Some(source) = self
+            .get_object(GetObjectRequest {
+                bucket: request.source_bucket.clone(),
+                key: request.source_key.clone(),
+                ..Default::default()
+            })
+            .await?
+        else {
+            return Err(Error::NotFound {
+                bucket: request.source_bucket,
+                key: request.source_key,
+            });
+        };
+
+        let attributes = request.resolve_attributes(&source.meta);
+        let body = source
+            .body
+            .collect()
+            .await
+            .map_err(Error::ByteStreamCollectionError)?
+            .into_bytes();
+
+        self.put_object(PutObjectRequest {
+            bucket: request.bucket,
+            key: request.key,
+            body,
+            attributes,
+            ..Default::default()
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// Deletes every key, reporting failures per key. Backends with a batch API should override
+    /// the default, which issues one `delete_object` per key.
+    async fn delete_objects(&self, bucket: &str, keys: Vec<String>) -> Result<DeleteReport, Error> {
+        let mut report = DeleteReport::default();
+        for key in keys {
+            match self.delete_object(bucket, &key).await {
+                Ok(()) => report.deleted.push(key),
+                Err(e) => report.failures.push(DeleteFailure {
+                    key,
+                    code: None,
+                    message: Some(e.to_string()),
+                }),
+            }
+        }
+        Ok(report)
+    }
+
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ListObjectsPage, Error>;
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        attributes: ObjectAttributes,
+    ) -> Result<String, Error>;
+
+    async fn upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Bytes,
+    ) -> Result<UploadedPart, Error>;
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<UploadedPart>,
+    ) -> Result<(), Error>;
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<(), Error>;
+
+    async fn presign_get_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        expires_in: Duration,
+        options: PresignedUrlOptions,
+    ) -> Result<String, Error> {
+        let _ = (bucket, key, expires_in, options);
+        Err(Error::Unsupported(String::from("presigned urls")))
+    }
+
+    /// Presigns a PUT of `key`. When `content_type` is given the upload must send the same
+    /// `Content-Type` header.
+    async fn presign_put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        content_type: Option<String>,
+        expires_in: Duration,
+    ) -> Result<String, Error> {
+        let _ = (bucket, key, content_type, expires_in);
+        Err(Error::Unsupported(String::from("presigned urls")))
+    }
+
+    async fn presign_post_object(
+        &self,
+        bucket: &str,
+        policy: PostPolicy,
+    ) -> Result<PresignedPost, Error> {
+        let _ = (bucket, policy);
+        Err(Error::Unsupported(String::from("presigned posts")))
+    }
+
+    /// The AWS SDK client behind the store, if any. Wrapping stores return the client of the store
+    /// they wrap.
+    fn client(&self) -> Option<&Client> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObjectAttributes {
+    pub content_type: Option<String>,
+    pub metadata: HashMap<String, String>,
+    /// Storage class name, e.g. `STANDARD_IA`.
+    pub storage_class: Option<String>,
+    /// Server side encryption algorithm, e.g. `AES256` or `aws:kms`.
+    pub server_side_encryption: Option<String>,
+    pub sse_kms_key_id: Option<String>,
+}
+
+impl ObjectAttributes {
+    /// Fills the storage class and encryption settings left unset from `defaults`.
+    pub fn or_defaults(mut self, defaults: &ObjectAttributes) -> Self {
+        self.storage_class = self.storage_class.or(defaults.storage_class.clone());
+        self.server_side_encryption = self
+            .server_side_encryption
+            .or(defaults.server_side_encryption.clone());
+        self.sse_kms_key_id = self.sse_kms_key_id.or(defaults.sse_kms_key_id.clone());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PutObjectRequest {
+    pub bucket: String,
+    pub key: String,
+    pub body: Bytes,
+    pub attributes: ObjectAttributes,
+    pub if_match: Option<String>,
+    pub if_none_match: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PutObjectResponse {
+    pub etag: Option<String>,
+}
+
+/// Copies `source_bucket/source_key` to `bucket/key`. The source's content type and metadata are
+/// kept unless `attributes` replaces them.
+#[derive(Debug, Clone, Default)]
+pub struct CopyObjectRequest {
+    pub source_bucket: String,
+    pub source_key: String,
+    pub bucket: String,
+    pub key: String,
+    pub attributes: Option<ObjectAttributes>,
+    /// Storage class and encryption used when neither `attributes` nor the source set them.
+    pub default_attributes: ObjectAttributes,
+}
+
+impl CopyObjectRequest {
+    /// Attributes of the copy of `source`. The source's storage class is carried over unless
+    /// `attributes` sets one; S3 reports none for `STANDARD` objects, so those get the default
+    /// storage class like any object without one.
+    fn resolve_attributes(&self, source: &S3ObjectMeta) -> ObjectAttributes {
+        let mut attributes = self.attributes.clone().unwrap_or(ObjectAttributes {
+            content_type: source.content_type.clone(),
+            metadata: source.metadata.clone(),
+            ..Default::default()
+        });
+        attributes.storage_class = attributes
+            .storage_class
+            .or(source.storage_class.clone().filter(|x| x != "STANDARD"));
+        attributes.or_defaults(&self.default_attributes)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GetObjectRequest {
+    pub bucket: String,
+    pub key: String,
+    pub range: Option<ByteRange>,
+    pub if_match: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct GetObjectResponse {
+    pub body: ByteStream,
+    /// Metadata of the response. For ranged requests `size` is the length of the range.
+    pub meta: S3ObjectMeta,
+    pub content_range: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ListObjectsPage {
+    pub keys: Vec<String>,
+    pub next_continuation_token: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UploadedPart {
+    pub part_number: i32,
+    pub etag: String,
+}
+
+/// Applies S3's `If-Match` / `If-None-Match: *` semantics against the ETag of the current object.
+fn check_preconditions(
+    request: &PutObjectRequest,
+    existing_etag: Option<&str>,
+) -> Result<(), Error> {
+    let matches = match (request.if_match.as_deref(), existing_etag) {
+        (Some(etag), Some(existing)) => existing == etag,
+        (Some(_), None) => false,
+        (None, _) => true,
+    };
+    let is_absent = request.if_none_match.is_none() || existing_etag.is_none();
+    if !matches || !is_absent {
+        return Err(Error::PreconditionFailed {
+            bucket: request.bucket.clone(),
+            key: request.key.clone(),
+        });
+    }
+    Ok(())
+}
+
+fn md5_etag(bytes: &[u8]) -> String {
+    format!("\"{:x}\"", Md5::digest(bytes))
+}
+
+/// Builds the ETag of a multipart object: the md5 of the concatenated part digests, suffixed with
+/// the part count.
+#[derive(Default)]
+struct MultipartEtag {
+    digests: Vec<u8>,
+    count: usize,
+}
+
+impl MultipartEtag {
+    fn push(&mut self, part: &[u8]) {
+        self.digests.extend_from_slice(&Md5::digest(part));
+        self.count += 1;
+    }
+
+    fn finish(self) -> String {
+        format!("\"{:x}-{}\"", Md5::digest(&self.digests), self.count)
+    }
+}
+
+/// Resolves the range against an object of `size` bytes into inclusive offsets.
+fn resolve_range(range: ByteRange, size: u64) -> Option<(u64, u64)> {
+    let last = size.checked_sub(1)?;
+    let (start, end) = match range {
+        ByteRange::Inclusive { start, end } => (start, end.min(last)),
+        ByteRange::From { start } => (start, last),
+        ByteRange::Last { length } => (size - length.min(size), last),
+    };
+    (start <= end).then_some((start, end))
+}
+
+fn range_not_satisfiable(range: ByteRange, key: &str) -> Error {
+    Error::Other(format!(
+        "Range `{}` is not satisfiable for key `{key}`.",
+        range.to_header()
+    ))
+}
+
+fn no_such_upload(upload_id: &str) -> Error {
+    Error::Other(format!("Multipart upload `{upload_id}` does not exist."))
+}
+
+fn missing_part(part_number: i32) -> Error {
+    Error::Other(format!("Part {part_number} was not uploaded."))
+}
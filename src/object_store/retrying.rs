@@ -0,0 +1,203 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use aws_sdk_s3::Client;
+use bytes::Bytes;
+
+use crate::{
+    delete_report::DeleteReport,
+    error::Error,
+    post_policy::{PostPolicy, PresignedPost},
+    presigned_url_options::PresignedUrlOptions,
+    retry_policy::RetryPolicy,
+    s3_object_meta::S3ObjectMeta,
+};
+
+use super::{
+    CopyObjectRequest, GetObjectRequest, GetObjectResponse, ListObjectsPage, ObjectAttributes,
+    ObjectStore, PutObjectRequest, PutObjectResponse, UploadedPart,
+};
+
+/// Wraps another store and retries its operations according to a `RetryPolicy`. Only the request
+/// itself is retried: errors while reading a returned body reach the caller.
+#[derive(Debug, Clone)]
+pub struct RetryingObjectStore {
+    pub inner: Arc<dyn ObjectStore>,
+    pub policy: RetryPolicy,
+}
+
+impl RetryingObjectStore {
+    pub fn new(inner: Arc<dyn ObjectStore>, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    async fn retry<T, F, Fut>(&self, operation: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        self.retry_if(operation, |_| true).await
+    }
+
+    /// Like `retry`, also requiring `filter` to accept the error.
+    async fn retry_if<T, F, Fut>(
+        &self,
+        mut operation: F,
+        filter: impl Fn(&Error) -> bool,
+    ) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Err(e) if filter(&e) && self.policy.should_retry(&e, attempt) => {
+                    tokio::time::sleep(self.policy.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for RetryingObjectStore {
+    /// Conditional puts are only retried when throttled. Any other failure, e.g. a timeout, may
+    /// come after the put was applied, and retrying it would then fail its own precondition.
+    async fn put_object(&self, request: PutObjectRequest) -> Result<PutObjectResponse, Error> {
+        let is_conditional = request.if_match.is_some() || request.if_none_match.is_some();
+        self.retry_if(
+            || self.inner.put_object(request.clone()),
+            |e| !is_conditional || e.is_throttling(),
+        )
+        .await
+    }
+
+    async fn get_object(
+        &self,
+        request: GetObjectRequest,
+    ) -> Result<Option<GetObjectResponse>, Error> {
+        self.retry(|| self.inner.get_object(request.clone())).await
+    }
+
+    async fn head_object(&self, bucket: &str, key: &str) -> Result<Option<S3ObjectMeta>, Error> {
+        self.retry(|| self.inner.head_object(bucket, key)).await
+    }
+
+    async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), Error> {
+        self.retry(|| self.inner.delete_object(bucket, key)).await
+    }
+
+    async fn copy_object(&self, request: CopyObjectRequest) -> Result<(), Error> {
+        self.retry(|| self.inner.copy_object(request.clone())).await
+    }
+
+    async fn delete_objects(&self, bucket: &str, keys: Vec<String>) -> Result<DeleteReport, Error> {
+        self.retry(|| self.inner.delete_objects(bucket, keys.clone()))
+            .await
+    }
+
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ListObjectsPage, Error> {
+        self.retry(|| {
+            self.inner
+                .list_objects(bucket, prefix, continuation_token.clone())
+        })
+        .await
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        attributes: ObjectAttributes,
+    ) -> Result<String, Error> {
+        self.retry(|| {
+            self.inner
+                .create_multipart_upload(bucket, key, attributes.clone())
+        })
+        .await
+    }
+
+    async fn upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Bytes,
+    ) -> Result<UploadedPart, Error> {
+        self.retry(|| {
+            self.inner
+                .upload_part(bucket, key, upload_id, part_number, body.clone())
+        })
+        .await
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<UploadedPart>,
+    ) -> Result<(), Error> {
+        self.retry(|| {
+            self.inner
+                .complete_multipart_upload(bucket, key, upload_id, parts.clone())
+        })
+        .await
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<(), Error> {
+        self.retry(|| self.inner.abort_multipart_upload(bucket, key, upload_id))
+            .await
+    }
+
+    // Presigning happens locally, there is nothing to retry.
+    async fn presign_get_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        expires_in: Duration,
+        options: PresignedUrlOptions,
+    ) -> Result<String, Error> {
+        self.inner
+            .presign_get_object(bucket, key, expires_in, options)
+            .await
+    }
+
+    async fn presign_put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        content_type: Option<String>,
+        expires_in: Duration,
+    ) -> Result<String, Error> {
+        self.inner
+            .presign_put_object(bucket, key, content_type, expires_in)
+            .await
+    }
+
+    async fn presign_post_object(
+        &self,
+        bucket: &str,
+        policy: PostPolicy,
+    ) -> Result<PresignedPost, Error> {
+        self.inner.presign_post_object(bucket, policy).await
+    }
+
+    fn client(&self) -> Option<&Client> {
+        self.inner.client()
+    }
+}
@@ -0,0 +1,495 @@
+//! Behaviour every `ObjectStore` has to share, run against the bundled local stores.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+
+use crate::{
+    byte_range::ByteRange,
+    error::{Error, KeyBuildError},
+    s3_context::s3_bucket::S3Bucket,
+    s3_object::S3Object,
+};
+
+use super::{
+    ConfiguredObjectStore, CopyObjectRequest, FsObjectStore, GetObjectRequest, InMemoryObjectStore,
+    ObjectStore, PutObjectRequest, UploadedPart,
+};
+
+const BUCKET: &str = "bucket";
+
+fn put_request(key: &str, body: &'static [u8]) -> PutObjectRequest {
+    PutObjectRequest {
+        bucket: BUCKET.to_string(),
+        key: key.to_string(),
+        body: Bytes::from_static(body),
+        ..Default::default()
+    }
+}
+
+fn get_request(key: &str) -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: BUCKET.to_string(),
+        key: key.to_string(),
+        ..Default::default()
+    }
+}
+
+async fn read(store: &dyn ObjectStore, key: &str) -> Option<Bytes> {
+    let response = store.get_object(get_request(key)).await.unwrap()?;
+    Some(response.body.collect().await.unwrap().into_bytes())
+}
+
+async fn put(store: &dyn ObjectStore, key: &str, body: &'static [u8]) -> String {
+    let response = store.put_object(put_request(key, body)).await.unwrap();
+    response.etag.unwrap()
+}
+
+struct Raw(Bytes);
+
+impl From<S3Object> for Raw {
+    fn from(value: S3Object) -> Self {
+        Self(value.bytes)
+    }
+}
+
+async fn etags(store: &dyn ObjectStore) {
+    // md5 of "hello", quoted like S3 does.
+    let etag = put(store, "a.txt", b"hello").await;
+    assert_eq!(etag, "\"5d41402abc4b2a76b9719d911017c592\"");
+
+    let meta = store.head_object(BUCKET, "a.txt").await.unwrap().unwrap();
+    assert_eq!(meta.etag.as_deref(), Some(etag.as_str()));
+    assert_eq!(meta.size, 5);
+    let response = store
+        .get_object(get_request("a.txt"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.meta.etag.as_deref(), Some(etag.as_str()));
+
+    let replaced = put(store, "a.txt", b"world").await;
+    assert_ne!(replaced, etag);
+    assert_eq!(read(store, "a.txt").await.unwrap(), "world");
+}
+
+async fn put_preconditions(store: &dyn ObjectStore) {
+    let etag = put(store, "a.txt", b"first").await;
+
+    let if_absent = PutObjectRequest {
+        if_none_match: Some(String::from("*")),
+        ..put_request("a.txt", b"second")
+    };
+    let e = store.put_object(if_absent.clone()).await.unwrap_err();
+    assert!(e.is_precondition_failed(), "{e}");
+    assert_eq!(e.key(), Some("a.txt"));
+
+    let stale = PutObjectRequest {
+        if_match: Some(String::from("\"stale\"")),
+        ..put_request("a.txt", b"second")
+    };
+    assert!(
+        store
+            .put_object(stale)
+            .await
+            .unwrap_err()
+            .is_precondition_failed()
+    );
+    assert_eq!(read(store, "a.txt").await.unwrap(), "first");
+
+    let current = PutObjectRequest {
+        if_match: Some(etag),
+        ..put_request("a.txt", b"second")
+    };
+    store.put_object(current).await.unwrap();
+    assert_eq!(read(store, "a.txt").await.unwrap(), "second");
+
+    let absent = PutObjectRequest {
+        key: String::from("b.txt"),
+        ..if_absent
+    };
+    store.put_object(absent).await.unwrap();
+
+    let missing = PutObjectRequest {
+        if_match: Some(String::from("\"any\"")),
+        ..put_request("c.txt", b"third")
+    };
+    assert!(
+        store
+            .put_object(missing)
+            .await
+            .unwrap_err()
+            .is_precondition_failed()
+    );
+    assert!(read(store, "c.txt").await.is_none());
+}
+
+async fn get_preconditions(store: &dyn ObjectStore) {
+    let etag = put(store, "a.txt", b"hello").await;
+
+    let request = GetObjectRequest {
+        if_match: Some(etag),
+        ..get_request("a.txt")
+    };
+    assert!(store.get_object(request).await.unwrap().is_some());
+
+    let request = GetObjectRequest {
+        if_match: Some(String::from("\"stale\"")),
+        ..get_request("a.txt")
+    };
+    let e = store.get_object(request).await.unwrap_err();
+    assert!(e.is_precondition_failed(), "{e}");
+}
+
+async fn not_found(store: &dyn ObjectStore) {
+    assert!(
+        store
+            .get_object(get_request("missing"))
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        store
+            .head_object(BUCKET, "missing")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    store.delete_object(BUCKET, "missing").await.unwrap();
+
+    let e = store
+        .copy_object(CopyObjectRequest {
+            source_bucket: BUCKET.to_string(),
+            source_key: String::from("missing"),
+            bucket: BUCKET.to_string(),
+            key: String::from("copy"),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert!(e.is_not_found(), "{e}");
+
+    let bucket = S3Bucket::new(BUCKET.to_string(), store);
+    let e = bucket
+        .get::<Raw>(String::from("missing"))
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        matches!(e, Error::NotFound { ref key, .. } if key == "missing"),
+        "{e}"
+    );
+    assert!(e.is_not_found());
+    assert!(
+        bucket
+            .get_maybe::<Raw>(String::from("missing"))
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    put(store, "deleted", b"x").await;
+    let found = bucket.get::<Raw>(String::from("deleted")).await.unwrap();
+    assert_eq!(found.0, "x");
+    store.delete_object(BUCKET, "deleted").await.unwrap();
+    assert!(bucket.get::<Raw>(String::from("deleted")).await.is_err());
+}
+
+async fn ranges(store: &dyn ObjectStore) {
+    put(store, "a.txt", b"hello world").await;
+    let bucket = S3Bucket::new(BUCKET.to_string(), store);
+
+    let range = bucket
+        .get_range(String::from("a.txt"), 6..11)
+        .await
+        .unwrap();
+    assert_eq!(range.bytes, "world");
+    assert_eq!((range.start, range.total_size), (6, 11));
+    let range = bucket
+        .get_range(String::from("a.txt"), 0..=0)
+        .await
+        .unwrap();
+    assert_eq!(range.bytes, "h");
+    let range = bucket.get_range(String::from("a.txt"), 9..).await.unwrap();
+    assert_eq!(range.bytes, "ld");
+    let range = bucket
+        .get_range(String::from("a.txt"), ByteRange::last(3))
+        .await
+        .unwrap();
+    assert_eq!(range.bytes, "rld");
+
+    for e in [
+        bucket.get_range(String::from("a.txt"), 0..0).await.err(),
+        bucket.get_range(String::from("a.txt"), 5..5).await.err(),
+        #[allow(clippy::reversed_empty_ranges)]
+        bucket.get_range(String::from("a.txt"), 5..=4).await.err(),
+    ] {
+        assert!(matches!(e, Some(Error::EmptyRange(_))), "{e:?}");
+    }
+}
+
+async fn list_pagination(store: &dyn ObjectStore) {
+    let mut expected = vec![];
+    for index in 0..1003 {
+        let key = format!("logs/{index:04}.txt");
+        store
+            .put_object(PutObjectRequest {
+                key: key.clone(),
+                ..put_request("", b"x")
+            })
+            .await
+            .unwrap();
+        expected.push(key);
+    }
+    put(store, "logs-archive.txt", b"x").await;
+    put(store, "other/0000.txt", b"x").await;
+
+    let first = store.list_objects(BUCKET, "logs/", None).await.unwrap();
+    assert_eq!(first.keys, expected[..1000]);
+    assert_eq!(first.next_continuation_token.as_ref(), expected.get(999));
+
+    let second = store
+        .list_objects(BUCKET, "logs/", first.next_continuation_token)
+        .await
+        .unwrap();
+    assert_eq!(second.keys, expected[1000..]);
+    assert_eq!(second.next_continuation_token, None);
+
+    // Keys sort bytewise, so `logs-archive.txt` comes before `logs/...`.
+    let all = store.list_objects(BUCKET, "logs", None).await.unwrap();
+    assert_eq!(all.keys[0], "logs-archive.txt");
+    assert_eq!(all.keys[1..], expected[..999]);
+
+    let after = store
+        .list_objects(BUCKET, "logs/", Some(String::from("logs/0500")))
+        .await
+        .unwrap();
+    assert_eq!(after.keys, expected[500..]);
+
+    let nothing = store.list_objects(BUCKET, "none/", None).await.unwrap();
+    assert!(nothing.keys.is_empty());
+    assert_eq!(nothing.next_continuation_token, None);
+}
+
+async fn multipart(store: &dyn ObjectStore) {
+    let upload_id = store
+        .create_multipart_upload(BUCKET, "big", Default::default())
+        .await
+        .unwrap();
+    let mut parts = vec![];
+    for (part_number, body) in [(1, "hello "), (2, "world")] {
+        let part = store
+            .upload_part(BUCKET, "big", &upload_id, part_number, Bytes::from(body))
+            .await
+            .unwrap();
+        parts.push(part);
+    }
+
+    let mut with_missing = parts.clone();
+    with_missing.push(UploadedPart {
+        part_number: 3,
+        etag: String::from("\"missing\""),
+    });
+    let e = store
+        .complete_multipart_upload(BUCKET, "big", &upload_id, with_missing)
+        .await
+        .unwrap_err();
+    assert!(e.to_string().contains("Part 3"), "{e}");
+    assert!(read(store, "big").await.is_none());
+
+    store
+        .complete_multipart_upload(BUCKET, "big", &upload_id, parts)
+        .await
+        .unwrap();
+    assert_eq!(read(store, "big").await.unwrap(), "hello world");
+    let meta = store.head_object(BUCKET, "big").await.unwrap().unwrap();
+    assert!(meta.etag.unwrap().ends_with("-2\""));
+
+    let e = store
+        .upload_part(BUCKET, "big", &upload_id, 1, Bytes::from("late"))
+        .await
+        .unwrap_err();
+    assert!(e.to_string().contains(&upload_id), "{e}");
+}
+
+async fn aborted_multipart(store: &dyn ObjectStore) {
+    let upload_id = store
+        .create_multipart_upload(BUCKET, "big", Default::default())
+        .await
+        .unwrap();
+    let part = store
+        .upload_part(BUCKET, "big", &upload_id, 1, Bytes::from("data"))
+        .await
+        .unwrap();
+    store
+        .abort_multipart_upload(BUCKET, "big", &upload_id)
+        .await
+        .unwrap();
+
+    assert!(
+        store
+            .complete_multipart_upload(BUCKET, "big", &upload_id, vec![part])
+            .await
+            .is_err()
+    );
+    assert!(read(store, "big").await.is_none());
+}
+
+macro_rules! store_suite {
+    ($name:ident, $store:expr) => {
+        mod $name {
+            use super::*;
+
+            #[tokio::test]
+            async fn etags() {
+                let (store, _guard) = $store;
+                super::etags(&store).await;
+            }
+
+            #[tokio::test]
+            async fn put_preconditions() {
+                let (store, _guard) = $store;
+                super::put_preconditions(&store).await;
+            }
+
+            #[tokio::test]
+            async fn get_preconditions() {
+                let (store, _guard) = $store;
+                super::get_preconditions(&store).await;
+            }
+
+            #[tokio::test]
+            async fn not_found() {
+                let (store, _guard) = $store;
+                super::not_found(&store).await;
+            }
+
+            #[tokio::test]
+            async fn ranges() {
+                let (store, _guard) = $store;
+                super::ranges(&store).await;
+            }
+
+            #[tokio::test]
+            async fn list_pagination() {
+                let (store, _guard) = $store;
+                super::list_pagination(&store).await;
+            }
+
+            #[tokio::test]
+            async fn multipart() {
+                let (store, _guard) = $store;
+                super::multipart(&store).await;
+            }
+
+            #[tokio::test]
+            async fn aborted_multipart() {
+                let (store, _guard) = $store;
+                super::aborted_multipart(&store).await;
+            }
+        }
+    };
+}
+
+store_suite!(in_memory, (InMemoryObjectStore::new(), ()));
+
+store_suite!(fs, {
+    let dir = tempfile::tempdir().unwrap();
+    (FsObjectStore::new(dir.path()), dir)
+});
+
+#[tokio::test]
+async fn configured_store_checks_the_length_of_prefixed_keys() {
+    let store = ConfiguredObjectStore {
+        key_prefix: String::from("dev/"),
+        ..ConfiguredObjectStore::new(Arc::new(InMemoryObjectStore::new()))
+    };
+
+    let key = "x".repeat(1020);
+    put(&store, &key, b"x").await;
+    assert_eq!(read(&store, &key).await.unwrap(), "x");
+
+    let key = "x".repeat(1021);
+    let e = store.put_object(put_request(&key, b"x")).await.unwrap_err();
+    assert!(
+        matches!(
+            e,
+            Error::KeyBuildError(KeyBuildError::TooLong { length: 1025, .. })
+        ),
+        "{e}"
+    );
+    assert!(store.head_object(BUCKET, &key).await.is_err());
+}
+
+#[tokio::test]
+async fn fs_rejects_file_and_directory_conflicts() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = FsObjectStore::new(dir.path());
+
+    put(&store, "a/b", b"x").await;
+    let e = store.put_object(put_request("a", b"y")).await.unwrap_err();
+    assert!(
+        matches!(e, Error::InvalidKey { ref key, .. } if key == "a"),
+        "{e}"
+    );
+
+    put(&store, "c", b"x").await;
+    let e = store
+        .put_object(put_request("c/d", b"y"))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(e, Error::InvalidKey { ref key, .. } if key == "c/d"),
+        "{e}"
+    );
+    assert_eq!(read(&store, "c").await.unwrap(), "x");
+}
+
+#[tokio::test]
+async fn fs_keeps_sidecars_of_similar_keys_apart() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = FsObjectStore::new(dir.path());
+
+    let first = put(&store, "a", b"first").await;
+    let second = put(&store, "a.json/b", b"second").await;
+    let page = store.list_objects(BUCKET, "", None).await.unwrap();
+    assert_eq!(page.keys, vec!["a", "a.json/b"]);
+
+    let meta = store.head_object(BUCKET, "a").await.unwrap().unwrap();
+    assert_eq!(meta.etag, Some(first));
+    let meta = store
+        .head_object(BUCKET, "a.json/b")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(meta.etag, Some(second));
+
+    store.delete_object(BUCKET, "a").await.unwrap();
+    let meta = store
+        .head_object(BUCKET, "a.json/b")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(meta.size, 6);
+}
+
+#[tokio::test]
+async fn fs_reads_files_without_sidecar() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(BUCKET).join("dropped")).unwrap();
+    std::fs::write(dir.path().join(BUCKET).join("dropped/in"), "hello").unwrap();
+    let store = FsObjectStore::new(dir.path());
+
+    let meta = store
+        .head_object(BUCKET, "dropped/in")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        meta.etag.as_deref(),
+        Some("\"5d41402abc4b2a76b9719d911017c592\"")
+    );
+    let page = store.list_objects(BUCKET, "", None).await.unwrap();
+    assert_eq!(page.keys, vec!["dropped/in"]);
+}
@@ -0,0 +1,83 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use serde_json::{Value, json};
+
+/// Constraint on the key of an object uploaded through a presigned POST.
+#[derive(Debug, Clone)]
+pub enum KeyCondition {
+    Exact(String),
+    StartsWith(String),
+}
+
+/// Conditions of a presigned POST upload, checked by S3 when the form is submitted.
+#[derive(Debug, Clone)]
+pub struct PostPolicy {
+    pub expires_in: Duration,
+    pub key: Option<KeyCondition>,
+    /// Inclusive bounds of the accepted upload size in bytes.
+    pub content_length_range: Option<(u64, u64)>,
+    pub content_type: Option<String>,
+}
+
+impl PostPolicy {
+    pub fn new(expires_in: Duration) -> Self {
+        Self {
+            expires_in,
+            key: None,
+            content_length_range: None,
+            content_type: None,
+        }
+    }
+
+    pub fn with_key(mut self, key: String) -> Self {
+        self.key = Some(KeyCondition::Exact(key));
+        self
+    }
+
+    pub fn with_key_prefix(mut self, prefix: String) -> Self {
+        self.key = Some(KeyCondition::StartsWith(prefix));
+        self
+    }
+
+    pub fn with_content_length_range(mut self, min: u64, max: u64) -> Self {
+        self.content_length_range = Some((min, max));
+        self
+    }
+
+    pub fn with_content_type(mut self, content_type: String) -> Self {
+        self.content_type = Some(content_type);
+        self
+    }
+
+    /// Form fields with a fixed value. Each of them is also added to the policy as an exact match.
+    pub fn fields(&self) -> BTreeMap<String, String> {
+        let mut fields = BTreeMap::new();
+        if let Some(KeyCondition::Exact(key)) = &self.key {
+            fields.insert(String::from("key"), key.clone());
+        }
+        if let Some(content_type) = &self.content_type {
+            fields.insert(String::from("Content-Type"), content_type.clone());
+        }
+        fields
+    }
+
+    /// Policy conditions besides the exact matches of `fields`.
+    pub fn conditions(&self, bucket: &str) -> Vec<Value> {
+        let mut conditions = vec![json!({ "bucket": bucket })];
+        if let Some(KeyCondition::StartsWith(prefix)) = &self.key {
+            conditions.push(json!(["starts-with", "$key", prefix]));
+        }
+        if let Some((min, max)) = self.content_length_range {
+            conditions.push(json!(["content-length-range", min, max]));
+        }
+        conditions
+    }
+}
+
+/// A presigned POST upload: submit a multipart form to `url` with `fields` followed by the `file`
+/// field. With a key prefix condition the form must also set `key`.
+#[derive(Debug, Clone)]
+pub struct PresignedPost {
+    pub url: String,
+    pub fields: BTreeMap<String, String>,
+}
@@ -0,0 +1,46 @@
+/// Response header overrides baked into a presigned GET url.
+#[derive(Debug, Clone, Default)]
+pub struct PresignedUrlOptions {
+    pub response_content_disposition: Option<String>,
+    pub response_content_type: Option<String>,
+}
+
+impl PresignedUrlOptions {
+    pub fn with_response_content_disposition(mut self, content_disposition: String) -> Self {
+        self.response_content_disposition = Some(content_disposition);
+        self
+    }
+
+    pub fn with_response_content_type(mut self, content_type: String) -> Self {
+        self.response_content_type = Some(content_type);
+        self
+    }
+
+    /// Makes browsers download the object as `filename` instead of displaying it.
+    pub fn with_attachment_filename(self, filename: &str) -> Self {
+        self.with_response_content_disposition(attachment(filename))
+    }
+}
+
+/// `attachment` disposition with an ASCII fallback `filename` and the exact name in `filename*`.
+fn attachment(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|x| match x {
+            ' '..='~' if x != '"' && x != '\\' => x,
+            _ => '_',
+        })
+        .collect();
+
+    let mut encoded = String::new();
+    for byte in filename.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    format!("attachment; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
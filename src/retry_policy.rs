@@ -0,0 +1,79 @@
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+use crate::error::Error;
+
+/// Decides whether a failed operation should be attempted again.
+pub type RetryClassifier = Arc<dyn Fn(&Error) -> bool + Send + Sync>;
+
+/// Retries applied by `S3Context` to every storage operation, on top of whatever the SDK client
+/// does on its own.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Upper bound of the delay before the first retry. It doubles with every further retry.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub classifier: RetryClassifier,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            classifier: Arc::new(Error::is_retryable),
+        }
+    }
+}
+
+impl Debug for RetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RetryPolicy {
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn with_classifier(
+        mut self,
+        classifier: impl Fn(&Error) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.classifier = Arc::new(classifier);
+        self
+    }
+
+    /// Whether `error`, returned by attempt number `attempt` (starting at 1), should be retried.
+    pub fn should_retry(&self, error: &Error, attempt: u32) -> bool {
+        attempt < self.max_attempts && (self.classifier)(error)
+    }
+
+    /// Delay before the retry following attempt number `attempt`: exponential backoff with full
+    /// jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff);
+        ceiling.mul_f64(fastrand::f64())
+    }
+}
@@ -1,41 +1,134 @@
-use std::time::Duration;
+use std::{path::Path, sync::Arc, time::Duration};
 
 use aws_sdk_s3::Client;
 use bytes::Bytes;
-use s3_bucket::S3Bucket;
+use futures::Stream;
+use s3_bucket::{DEFAULT_UPDATE_MAX_ATTEMPTS, S3Bucket};
+use tokio::io::{AsyncRead, AsyncWrite};
 
 use crate::{
-    error::Error, s3_object::S3Object, traits::{
+    byte_range::IntoByteRange,
+    delete_report::DeleteReport,
+    download_options::DownloadOptions,
+    error::{BoxError, Error},
+    object_store::{AwsObjectStore, ObjectAttributes, ObjectStore},
+    post_policy::{PostPolicy, PresignedPost},
+    presigned_url_options::PresignedUrlOptions,
+    s3_object::{S3Object, S3ObjectRange},
+    s3_object_meta::S3ObjectMeta,
+    traits::{
         has_bucket_name::HasBucketName, has_content_type::HasContentType, has_key::HasKey,
-        key_builder::KeyBuilder,
-    }
+        has_typed_key::HasTypedKey, key_builder::KeyBuilder,
+    },
+    upload_options::UploadOptions,
+    versioned_item::VersionedItem,
 };
 
 pub mod s3_bucket;
+mod s3_context_builder;
+
+pub use s3_context_builder::S3ContextBuilder;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct S3Context {
-    pub client: Client,
+    pub store: Arc<dyn ObjectStore>,
+    /// Bucket used by `default_bucket`, for items without a bucket of their own.
+    pub default_bucket: Option<String>,
 }
 
 impl S3Context {
     pub fn new(client: Client) -> Self {
-        Self { client }
+        Self::with_store(AwsObjectStore::new(client))
+    }
+
+    pub fn builder() -> S3ContextBuilder {
+        S3ContextBuilder::default()
+    }
+
+    /// Creates a context backed by any `ObjectStore`, e.g. `InMemoryObjectStore` in tests.
+    pub fn with_store(store: impl ObjectStore + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+            default_bucket: None,
+        }
+    }
+
+    /// The AWS SDK client of the store, for operations this crate doesn't wrap. `None` for stores
+    /// not backed by S3. Requests sent through it bypass the key prefix, bucket mapping, retries
+    /// and timeouts configured on the builder.
+    pub fn client(&self) -> Option<&Client> {
+        self.store.client()
     }
 
     pub fn with_bucket(&'_ self, bucket_name: String) -> S3Bucket<'_> {
-        S3Bucket::new(bucket_name, &self.client)
+        S3Bucket::new(bucket_name, self.store.as_ref())
+    }
+
+    /// The bucket configured with `S3ContextBuilder::with_default_bucket`, if any.
+    pub fn default_bucket(&'_ self) -> Option<S3Bucket<'_>> {
+        self.default_bucket
+            .clone()
+            .map(|x| S3Bucket::new(x, self.store.as_ref()))
     }
 
-    pub async fn put<T: HasKey + TryInto<Bytes> + HasContentType + HasBucketName>(
+    pub async fn put<
+        T: HasKey + TryInto<Bytes, Error = impl Into<BoxError>> + HasContentType + HasBucketName,
+    >(
         &self,
         item: T,
     ) -> Result<(), Error> {
         self.with_bucket(T::get_bucket_name()).put(item).await
     }
 
+    pub async fn put_if_match<
+        T: HasKey + TryInto<Bytes, Error = impl Into<BoxError>> + HasContentType + HasBucketName,
+    >(
+        &self,
+        item: T,
+        etag: String,
+    ) -> Result<(), Error> {
+        self.with_bucket(T::get_bucket_name())
+            .put_if_match(item, etag)
+            .await
+    }
+
+    pub async fn put_if_absent<
+        T: HasKey + TryInto<Bytes, Error = impl Into<BoxError>> + HasContentType + HasBucketName,
+    >(
+        &self,
+        item: T,
+    ) -> Result<(), Error> {
+        self.with_bucket(T::get_bucket_name())
+            .put_if_absent(item)
+            .await
+    }
+
+    pub async fn put_file<T: HasKey + HasContentType + HasBucketName>(
+        &self,
+        item: &T,
+        path: impl AsRef<Path>,
+        options: UploadOptions,
+    ) -> Result<(), Error> {
+        let key = item.try_get_key().map_err(Error::KeyBuildError)?;
+        self.with_bucket(T::get_bucket_name())
+            .put_file(key, item.content_type(), path, options)
+            .await
+    }
+
+    pub async fn put_reader<T: HasKey + HasContentType + HasBucketName>(
+        &self,
+        item: &T,
+        reader: impl AsyncRead + Unpin + Send,
+        options: UploadOptions,
+    ) -> Result<(), Error> {
+        let key = item.try_get_key().map_err(Error::KeyBuildError)?;
+        self.with_bucket(T::get_bucket_name())
+            .put_reader(key, item.content_type(), reader, options)
+            .await
+    }
+
     pub async fn get_with_partial_keys<
-        T: KeyBuilder + TryFrom<S3Object, Error = impl std::fmt::Debug> + HasBucketName,
+        T: KeyBuilder + TryFrom<S3Object, Error = impl Into<BoxError>> + HasBucketName,
     >(
         &self,
         partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
@@ -45,15 +138,187 @@ impl S3Context {
             .await
     }
 
-    pub async fn get<T: TryFrom<S3Object, Error = impl std::fmt::Debug> + HasBucketName>(
+    pub async fn get_by_key<
+        T: HasTypedKey + TryFrom<S3Object, Error = impl Into<BoxError>> + HasBucketName,
+    >(
+        &self,
+        key: T::TypedKey,
+    ) -> Result<T, Error> {
+        self.with_bucket(T::get_bucket_name()).get_by_key(key).await
+    }
+
+    pub async fn get<T: TryFrom<S3Object, Error = impl Into<BoxError>> + HasBucketName>(
         &self,
         key: String,
     ) -> Result<T, Error> {
         self.with_bucket(T::get_bucket_name()).get(key).await
     }
 
+    pub async fn get_range_with_partial_keys<T: KeyBuilder + HasBucketName>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        range: impl IntoByteRange,
+    ) -> Result<S3ObjectRange, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .get_range_with_partial_keys::<T>(partial_keys, range)
+            .await
+    }
+
+    pub async fn get_range_by_key<T: HasTypedKey + HasBucketName>(
+        &self,
+        key: T::TypedKey,
+        range: impl IntoByteRange,
+    ) -> Result<S3ObjectRange, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .get_range_by_key::<T>(key, range)
+            .await
+    }
+
+    pub async fn get_range<T: HasBucketName>(
+        &self,
+        key: String,
+        range: impl IntoByteRange,
+    ) -> Result<S3ObjectRange, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .get_range(key, range)
+            .await
+    }
+
+    pub async fn get_to_writer_by_key<T: HasTypedKey + HasBucketName>(
+        &self,
+        key: T::TypedKey,
+        writer: &mut (impl AsyncWrite + Unpin + Send),
+    ) -> Result<u64, Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
+        self.get_to_writer::<T>(key, writer).await
+    }
+
+    pub async fn get_to_writer<T: HasBucketName>(
+        &self,
+        key: String,
+        writer: &mut (impl AsyncWrite + Unpin + Send),
+    ) -> Result<u64, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .get_to_writer(key, writer)
+            .await
+    }
+
+    pub async fn download_to_path_by_key<T: HasTypedKey + HasBucketName>(
+        &self,
+        key: T::TypedKey,
+        path: impl AsRef<Path>,
+        options: DownloadOptions,
+    ) -> Result<u64, Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
+        self.download_to_path::<T>(key, path, options).await
+    }
+
+    pub async fn download_to_path<T: HasBucketName>(
+        &self,
+        key: String,
+        path: impl AsRef<Path>,
+        options: DownloadOptions,
+    ) -> Result<u64, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .download_to_path(key, path, options)
+            .await
+    }
+
+    pub async fn get_versioned_with_partial_keys<
+        T: KeyBuilder + TryFrom<S3Object, Error = impl Into<BoxError>> + HasBucketName,
+    >(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+    ) -> Result<VersionedItem<T>, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .get_versioned_with_partial_keys(partial_keys)
+            .await
+    }
+
+    pub async fn get_versioned_by_key<
+        T: HasTypedKey + TryFrom<S3Object, Error = impl Into<BoxError>> + HasBucketName,
+    >(
+        &self,
+        key: T::TypedKey,
+    ) -> Result<VersionedItem<T>, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .get_versioned_by_key(key)
+            .await
+    }
+
+    pub async fn get_versioned<
+        T: TryFrom<S3Object, Error = impl Into<BoxError>> + HasBucketName,
+    >(
+        &self,
+        key: String,
+    ) -> Result<VersionedItem<T>, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .get_versioned(key)
+            .await
+    }
+
+    pub async fn update<T>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        update: impl FnMut(&mut T),
+    ) -> Result<(), Error>
+    where
+        T: KeyBuilder
+            + HasKey
+            + HasContentType
+            + HasBucketName
+            + TryInto<Bytes>
+            + TryFrom<S3Object>,
+        <T as TryFrom<S3Object>>::Error: Into<BoxError>,
+        <T as TryInto<Bytes>>::Error: Into<BoxError>,
+    {
+        self.update_with_max_attempts(partial_keys, DEFAULT_UPDATE_MAX_ATTEMPTS, update)
+            .await
+    }
+
+    pub async fn update_with_max_attempts<T>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        max_attempts: usize,
+        update: impl FnMut(&mut T),
+    ) -> Result<(), Error>
+    where
+        T: KeyBuilder
+            + HasKey
+            + HasContentType
+            + HasBucketName
+            + TryInto<Bytes>
+            + TryFrom<S3Object>,
+        <T as TryFrom<S3Object>>::Error: Into<BoxError>,
+        <T as TryInto<Bytes>>::Error: Into<BoxError>,
+    {
+        self.with_bucket(T::get_bucket_name())
+            .update_with_partial_keys(partial_keys, max_attempts, update)
+            .await
+    }
+
+    pub async fn update_by_key<T>(
+        &self,
+        key: T::TypedKey,
+        update: impl FnMut(&mut T),
+    ) -> Result<(), Error>
+    where
+        T: HasTypedKey
+            + HasKey
+            + HasContentType
+            + HasBucketName
+            + TryInto<Bytes>
+            + TryFrom<S3Object>,
+        <T as TryFrom<S3Object>>::Error: Into<BoxError>,
+        <T as TryInto<Bytes>>::Error: Into<BoxError>,
+    {
+        self.with_bucket(T::get_bucket_name())
+            .update_by_key(key, DEFAULT_UPDATE_MAX_ATTEMPTS, update)
+            .await
+    }
+
     pub async fn get_maybe_with_partial_keys<
-        T: KeyBuilder + TryFrom<S3Object, Error = impl std::fmt::Debug> + HasBucketName,
+        T: KeyBuilder + TryFrom<S3Object, Error = impl Into<BoxError>> + HasBucketName,
     >(
         &self,
         partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
@@ -63,13 +328,111 @@ impl S3Context {
             .await
     }
 
-    pub async fn get_maybe<T: TryFrom<S3Object, Error = impl std::fmt::Debug> + HasBucketName>(
+    pub async fn get_maybe_by_key<
+        T: HasTypedKey + TryFrom<S3Object, Error = impl Into<BoxError>> + HasBucketName,
+    >(
+        &self,
+        key: T::TypedKey,
+    ) -> Result<Option<T>, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .get_maybe_by_key(key)
+            .await
+    }
+
+    pub async fn get_maybe<T: TryFrom<S3Object, Error = impl Into<BoxError>> + HasBucketName>(
         &self,
         key: String,
     ) -> Result<Option<T>, Error> {
         self.with_bucket(T::get_bucket_name()).get_maybe(key).await
     }
 
+    pub async fn head_with_partial_keys<T: KeyBuilder + HasBucketName>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+    ) -> Result<Option<S3ObjectMeta>, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .head_with_partial_keys::<T>(partial_keys)
+            .await
+    }
+
+    pub async fn head_by_key<T: HasTypedKey + HasBucketName>(
+        &self,
+        key: T::TypedKey,
+    ) -> Result<Option<S3ObjectMeta>, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .head_by_key::<T>(key)
+            .await
+    }
+
+    pub async fn head<T: HasBucketName>(&self, key: String) -> Result<Option<S3ObjectMeta>, Error> {
+        self.with_bucket(T::get_bucket_name()).head(key).await
+    }
+
+    pub async fn exists_with_partial_keys<T: KeyBuilder + HasBucketName>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+    ) -> Result<bool, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .exists_with_partial_keys::<T>(partial_keys)
+            .await
+    }
+
+    pub async fn exists_by_key<T: HasTypedKey + HasBucketName>(
+        &self,
+        key: T::TypedKey,
+    ) -> Result<bool, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .exists_by_key::<T>(key)
+            .await
+    }
+
+    pub async fn exists<T: HasBucketName>(&self, key: String) -> Result<bool, Error> {
+        self.with_bucket(T::get_bucket_name()).exists(key).await
+    }
+
+    pub async fn list_keys<T: KeyBuilder + HasBucketName>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+    ) -> Result<Vec<String>, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .list_keys::<T>(partial_keys)
+            .await
+    }
+
+    pub async fn list<
+        T: KeyBuilder + TryFrom<S3Object, Error = impl Into<BoxError>> + HasBucketName,
+    >(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+    ) -> Result<Vec<T>, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .list(partial_keys)
+            .await
+    }
+
+    pub fn stream_items_with_partial_keys<'a, T>(
+        &'a self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+    ) -> impl Stream<Item = Result<T, Error>> + use<'a, T>
+    where
+        T: KeyBuilder + TryFrom<S3Object> + HasBucketName,
+        T::Error: Into<BoxError>,
+    {
+        self.with_bucket(T::get_bucket_name())
+            .stream_items_with_partial_keys(partial_keys)
+    }
+
+    pub fn stream_items<'a, T>(
+        &'a self,
+        prefix: String,
+    ) -> impl Stream<Item = Result<T, Error>> + use<'a, T>
+    where
+        T: TryFrom<S3Object> + HasBucketName,
+        T::Error: Into<BoxError>,
+    {
+        self.with_bucket(T::get_bucket_name()).stream_items(prefix)
+    }
+
     pub async fn delete_with_partial_keys<T: KeyBuilder + HasBucketName>(
         &self,
         partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
@@ -79,10 +442,152 @@ impl S3Context {
             .await
     }
 
+    pub async fn delete_by_key<T: HasTypedKey + HasBucketName>(
+        &self,
+        key: T::TypedKey,
+    ) -> Result<(), Error> {
+        self.with_bucket(T::get_bucket_name())
+            .delete_by_key::<T>(key)
+            .await
+    }
+
     pub async fn delete<T: HasBucketName>(&self, key: String) -> Result<(), Error> {
         self.with_bucket(T::get_bucket_name()).delete(key).await
     }
 
+    /// Copies an item from the bucket of `T` to `destination_key` in `destination_bucket`,
+    /// keeping content type and metadata.
+    pub async fn copy<T: HasBucketName>(
+        &self,
+        key: String,
+        destination_bucket: String,
+        destination_key: String,
+    ) -> Result<(), Error> {
+        self.with_bucket(T::get_bucket_name())
+            .copy_to(key, destination_bucket, destination_key, None)
+            .await
+    }
+
+    pub async fn copy_with_attributes<T: HasBucketName>(
+        &self,
+        key: String,
+        destination_bucket: String,
+        destination_key: String,
+        attributes: ObjectAttributes,
+    ) -> Result<(), Error> {
+        self.with_bucket(T::get_bucket_name())
+            .copy_to(key, destination_bucket, destination_key, Some(attributes))
+            .await
+    }
+
+    pub async fn copy_by_key<T: HasTypedKey + HasBucketName>(
+        &self,
+        key: T::TypedKey,
+        destination: T::TypedKey,
+    ) -> Result<(), Error> {
+        self.with_bucket(T::get_bucket_name())
+            .copy_by_key::<T>(key, destination)
+            .await
+    }
+
+    pub async fn move_item<T: HasBucketName>(
+        &self,
+        key: String,
+        destination_key: String,
+    ) -> Result<(), Error> {
+        self.with_bucket(T::get_bucket_name())
+            .move_item(key, destination_key)
+            .await
+    }
+
+    pub async fn move_by_key<T: HasTypedKey + HasBucketName>(
+        &self,
+        key: T::TypedKey,
+        destination: T::TypedKey,
+    ) -> Result<(), Error> {
+        self.with_bucket(T::get_bucket_name())
+            .move_by_key::<T>(key, destination)
+            .await
+    }
+
+    pub async fn delete_many<T: HasBucketName>(
+        &self,
+        keys: Vec<String>,
+    ) -> Result<DeleteReport, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .delete_many(keys)
+            .await
+    }
+
+    pub async fn delete_prefix<T: KeyBuilder + HasBucketName>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        dry_run: bool,
+    ) -> Result<DeleteReport, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .delete_prefix::<T>(partial_keys, dry_run)
+            .await
+    }
+
+    pub async fn generate_presigned_url_with_partial_keys<T: KeyBuilder + HasBucketName>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        lifetime_duration: Duration,
+    ) -> Result<String, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .generate_presigned_url_with_partial_keys::<T>(partial_keys, lifetime_duration)
+            .await
+    }
+
+    pub async fn generate_presigned_url_with_partial_keys_with_options<
+        T: KeyBuilder + HasBucketName,
+    >(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        lifetime_duration: Duration,
+        options: PresignedUrlOptions,
+    ) -> Result<String, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .generate_presigned_url_with_partial_keys_with_options::<T>(
+                partial_keys,
+                lifetime_duration,
+                options,
+            )
+            .await
+    }
+
+    pub async fn generate_presigned_url_by_key<T: HasTypedKey + HasBucketName>(
+        &self,
+        key: T::TypedKey,
+        lifetime_duration: Duration,
+    ) -> Result<String, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .generate_presigned_url_by_key::<T>(key, lifetime_duration)
+            .await
+    }
+
+    pub async fn generate_presigned_url_by_key_with_options<T: HasTypedKey + HasBucketName>(
+        &self,
+        key: T::TypedKey,
+        lifetime_duration: Duration,
+        options: PresignedUrlOptions,
+    ) -> Result<String, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .generate_presigned_url_by_key_with_options::<T>(key, lifetime_duration, options)
+            .await
+    }
+
+    pub async fn generate_presigned_url_with_options<T: HasBucketName>(
+        &self,
+        key: String,
+        lifetime_duration: Duration,
+        options: PresignedUrlOptions,
+    ) -> Result<String, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .generate_presigned_url_with_options(key, lifetime_duration, options)
+            .await
+    }
+
     pub async fn generate_presigned_url<T: HasBucketName>(
         &self,
         key: String,
@@ -92,4 +597,68 @@ impl S3Context {
             .generate_presigned_url(key, lifetime_duration)
             .await
     }
+
+    pub async fn generate_presigned_put_url_with_partial_keys<
+        T: KeyBuilder + HasContentType + HasBucketName,
+    >(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        lifetime_duration: Duration,
+    ) -> Result<String, Error> {
+        let key = T::try_build_key(partial_keys).map_err(Error::KeyBuildError)?;
+        self.generate_presigned_put_url::<T>(key, lifetime_duration)
+            .await
+    }
+
+    pub async fn generate_presigned_put_url_by_key<
+        T: HasTypedKey + HasContentType + HasBucketName,
+    >(
+        &self,
+        key: T::TypedKey,
+        lifetime_duration: Duration,
+    ) -> Result<String, Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
+        self.generate_presigned_put_url::<T>(key, lifetime_duration)
+            .await
+    }
+
+    /// Presigns a PUT of `key` with the content type of `T`, which the upload must send as its
+    /// `Content-Type` header.
+    pub async fn generate_presigned_put_url<T: HasContentType + HasBucketName>(
+        &self,
+        key: String,
+        lifetime_duration: Duration,
+    ) -> Result<String, Error> {
+        self.with_bucket(T::get_bucket_name())
+            .generate_presigned_put_url(key, Some(T::get_content_type()), lifetime_duration)
+            .await
+    }
+
+    /// Presigns a POST upload into the bucket of `T`, restricted to keys starting with the prefix
+    /// built from `partial_keys`.
+    pub async fn generate_presigned_post_with_partial_keys<
+        T: KeyBuilder + HasContentType + HasBucketName,
+    >(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        policy: PostPolicy,
+    ) -> Result<PresignedPost, Error> {
+        let prefix = T::try_build_prefix(partial_keys).map_err(Error::KeyBuildError)?;
+        let policy = policy.with_key_prefix(prefix);
+        self.generate_presigned_post::<T>(policy).await
+    }
+
+    /// Presigns a POST upload into the bucket of `T`. The content type of `T` is required unless
+    /// the policy sets one.
+    pub async fn generate_presigned_post<T: HasContentType + HasBucketName>(
+        &self,
+        mut policy: PostPolicy,
+    ) -> Result<PresignedPost, Error> {
+        if policy.content_type.is_none() {
+            policy.content_type = Some(T::get_content_type());
+        }
+        self.with_bucket(T::get_bucket_name())
+            .generate_presigned_post(policy)
+            .await
+    }
 }
@@ -1,152 +1,692 @@
 use std::time::Duration;
 
-use aws_sdk_s3::{Client, error::SdkError, presigning::PresigningConfig, primitives::ByteStream};
 use bytes::Bytes;
+use futures::{Stream, StreamExt, TryStreamExt, stream};
 
 use crate::{
-    error::Error,
-    s3_object::S3Object,
-    traits::{has_content_type::HasContentType, has_key::HasKey, key_builder::KeyBuilder},
+    byte_range::{ByteRange, IntoByteRange},
+    delete_report::DeleteReport,
+    error::{BoxError, Error},
+    object_store::{
+        CopyObjectRequest, GetObjectRequest, GetObjectResponse, ObjectAttributes, ObjectStore,
+        PutObjectRequest,
+    },
+    post_policy::{PostPolicy, PresignedPost},
+    presigned_url_options::PresignedUrlOptions,
+    retry_policy::RetryPolicy,
+    s3_object::{S3Object, S3ObjectRange},
+    s3_object_meta::S3ObjectMeta,
+    traits::{
+        has_content_type::HasContentType, has_key::HasKey, has_typed_key::HasTypedKey,
+        key_builder::KeyBuilder,
+    },
+    versioned_item::VersionedItem,
 };
 
+mod download;
+mod upload;
+
+pub const DEFAULT_STREAM_CONCURRENCY: usize = 8;
+pub const DEFAULT_UPDATE_MAX_ATTEMPTS: usize = 5;
+
+#[derive(Clone)]
 pub struct S3Bucket<'a> {
     pub bucket_name: String,
-    pub client: &'a Client,
+    pub store: &'a dyn ObjectStore,
 }
 
 impl<'a> S3Bucket<'a> {
-    pub fn new(bucket_name: String, client: &'a Client) -> Self {
-        Self {
-            bucket_name,
-            client,
-        }
+    pub fn new(bucket_name: String, store: &'a dyn ObjectStore) -> Self {
+        Self { bucket_name, store }
+    }
+
+    pub async fn put<T: HasKey + TryInto<Bytes, Error = impl Into<BoxError>> + HasContentType>(
+        &self,
+        item: T,
+    ) -> Result<(), Error> {
+        self.put_with_condition(item, None, None).await
     }
 
-    pub async fn put<T: HasKey + TryInto<Bytes> + HasContentType>(
+    /// Writes the item only if the stored object still has the given ETag.
+    pub async fn put_if_match<
+        T: HasKey + TryInto<Bytes, Error = impl Into<BoxError>> + HasContentType,
+    >(
+        &self,
+        item: T,
+        etag: String,
+    ) -> Result<(), Error> {
+        self.put_with_condition(item, Some(etag), None).await
+    }
+
+    /// Writes the item only if no object exists under its key.
+    pub async fn put_if_absent<
+        T: HasKey + TryInto<Bytes, Error = impl Into<BoxError>> + HasContentType,
+    >(
         &self,
         item: T,
     ) -> Result<(), Error> {
-        let key = item.get_key();
-        let bytes: Bytes = item.try_into().map_err(|_| Error::TryIntoByteError)?;
-        self.client
-            .put_object()
-            .bucket(&self.bucket_name)
-            .key(key)
-            .body(ByteStream::from(bytes))
-            .content_type(T::get_content_type())
-            .send()
+        self.put_with_condition(item, None, Some(String::from("*")))
             .await
-            .map_err(|e| Error::PutError(e))?;
+    }
 
-        return Ok(());
+    async fn put_with_condition<
+        T: HasKey + TryInto<Bytes, Error = impl Into<BoxError>> + HasContentType,
+    >(
+        &self,
+        item: T,
+        if_match: Option<String>,
+        if_none_match: Option<String>,
+    ) -> Result<(), Error> {
+        let key = item.try_get_key().map_err(Error::KeyBuildError)?;
+        let content_type = item.content_type();
+        let bytes: Bytes = item.try_into().map_err(|e| Error::TryIntoByteError {
+            key: key.clone(),
+            bucket: self.bucket_name.clone(),
+            source: e.into(),
+        })?;
+        self.store
+            .put_object(PutObjectRequest {
+                bucket: self.bucket_name.clone(),
+                key,
+                body: bytes,
+                attributes: ObjectAttributes {
+                    content_type: Some(content_type),
+                    ..Default::default()
+                },
+                if_match,
+                if_none_match,
+            })
+            .await
+            .map(|_| ())
     }
 
     pub async fn get_with_partial_keys<
-        T: KeyBuilder + TryFrom<S3Object, Error = impl std::fmt::Debug>,
+        T: KeyBuilder + TryFrom<S3Object, Error = impl Into<BoxError>>,
     >(
         &self,
         partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
     ) -> Result<T, Error> {
-        let key = T::build_key(partial_keys);
+        let key = T::try_build_key(partial_keys).map_err(Error::KeyBuildError)?;
         self.get(key).await
     }
 
-    pub async fn get<T: TryFrom<S3Object, Error = impl std::fmt::Debug>>(
+    pub async fn get_by_key<T: HasTypedKey + TryFrom<S3Object, Error = impl Into<BoxError>>>(
+        &self,
+        key: T::TypedKey,
+    ) -> Result<T, Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
+        self.get(key).await
+    }
+
+    pub async fn get<T: TryFrom<S3Object, Error = impl Into<BoxError>>>(
         &self,
         key: String,
     ) -> Result<T, Error> {
-        let result = self
-            .client
-            .get_object()
-            .bucket(&self.bucket_name)
-            .key(&key)
-            .send()
-            .await
-            .map_err(|e| Error::GetError(e))?;
+        let Some((object, _)) = self.get_s3_object(key.clone()).await? else {
+            return Err(Error::NotFound {
+                bucket: self.bucket_name.clone(),
+                key,
+            });
+        };
 
+        self.decode(object)
+    }
+
+    pub async fn get_range_with_partial_keys<T: KeyBuilder>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        range: impl IntoByteRange,
+    ) -> Result<S3ObjectRange, Error> {
+        let key = T::try_build_key(partial_keys).map_err(Error::KeyBuildError)?;
+        self.get_range(key, range).await
+    }
+
+    pub async fn get_range_by_key<T: HasTypedKey>(
+        &self,
+        key: T::TypedKey,
+        range: impl IntoByteRange,
+    ) -> Result<S3ObjectRange, Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
+        self.get_range(key, range).await
+    }
+
+    pub async fn get_range(
+        &self,
+        key: String,
+        range: impl IntoByteRange,
+    ) -> Result<S3ObjectRange, Error> {
+        let range = range.into_byte_range()?;
+        self.get_range_if_match(key, range, None).await
+    }
+
+    async fn get_range_if_match(
+        &self,
+        key: String,
+        range: ByteRange,
+        etag: Option<String>,
+    ) -> Result<S3ObjectRange, Error> {
+        let Some(result) = self.get_object(key.clone(), Some(range), etag).await? else {
+            return Err(Error::NotFound {
+                bucket: self.bucket_name.clone(),
+                key,
+            });
+        };
+
+        let content_range = result.content_range;
         let bytes = result
             .body
             .collect()
             .await
-            .map_err(|_| Error::ByteStreamCollectionError)?
+            .map_err(Error::ByteStreamCollectionError)?
             .into_bytes();
 
-        let object = S3Object::new(bytes, key);
+        Ok(S3ObjectRange::new(bytes, key, content_range.as_deref()))
+    }
 
-        T::try_from(object).map_err(|e| {
-            eprintln!("{:?}", e);
-            Error::TryFromByteError
-        })
+    pub async fn get_versioned_with_partial_keys<
+        T: KeyBuilder + TryFrom<S3Object, Error = impl Into<BoxError>>,
+    >(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+    ) -> Result<VersionedItem<T>, Error> {
+        let key = T::try_build_key(partial_keys).map_err(Error::KeyBuildError)?;
+        self.get_versioned(key).await
+    }
+
+    pub async fn get_versioned_by_key<
+        T: HasTypedKey + TryFrom<S3Object, Error = impl Into<BoxError>>,
+    >(
+        &self,
+        key: T::TypedKey,
+    ) -> Result<VersionedItem<T>, Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
+        self.get_versioned(key).await
+    }
+
+    pub async fn get_versioned<T: TryFrom<S3Object, Error = impl Into<BoxError>>>(
+        &self,
+        key: String,
+    ) -> Result<VersionedItem<T>, Error> {
+        let Some((object, meta)) = self.get_s3_object(key.clone()).await? else {
+            return Err(Error::NotFound {
+                bucket: self.bucket_name.clone(),
+                key,
+            });
+        };
+
+        let Some(etag) = meta.etag else {
+            return Err(Error::MissingEtag {
+                bucket: self.bucket_name.clone(),
+                key,
+            });
+        };
+        let item = self.decode(object)?;
+        Ok(VersionedItem::new(item, etag))
+    }
+
+    pub async fn update_with_partial_keys<T>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        max_attempts: usize,
+        update: impl FnMut(&mut T),
+    ) -> Result<(), Error>
+    where
+        T: KeyBuilder + HasKey + HasContentType + TryInto<Bytes> + TryFrom<S3Object>,
+        <T as TryFrom<S3Object>>::Error: Into<BoxError>,
+        <T as TryInto<Bytes>>::Error: Into<BoxError>,
+    {
+        let key = T::try_build_key(partial_keys).map_err(Error::KeyBuildError)?;
+        self.update(key, max_attempts, update).await
+    }
+
+    pub async fn update_by_key<T>(
+        &self,
+        key: T::TypedKey,
+        max_attempts: usize,
+        update: impl FnMut(&mut T),
+    ) -> Result<(), Error>
+    where
+        T: HasTypedKey + HasKey + HasContentType + TryInto<Bytes> + TryFrom<S3Object>,
+        <T as TryFrom<S3Object>>::Error: Into<BoxError>,
+        <T as TryInto<Bytes>>::Error: Into<BoxError>,
+    {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
+        self.update(key, max_attempts, update).await
+    }
+
+    /// Reads the item, applies `update` and writes it back with `If-Match`. The whole cycle is
+    /// retried with backoff when another writer changed the item in between, up to `max_attempts`
+    /// times. `update` must not change the fields of the key.
+    pub async fn update<T>(
+        &self,
+        key: String,
+        max_attempts: usize,
+        mut update: impl FnMut(&mut T),
+    ) -> Result<(), Error>
+    where
+        T: HasKey + HasContentType + TryInto<Bytes> + TryFrom<S3Object>,
+        <T as TryFrom<S3Object>>::Error: Into<BoxError>,
+        <T as TryInto<Bytes>>::Error: Into<BoxError>,
+    {
+        let backoff = RetryPolicy::default();
+        let mut attempt = 1;
+        loop {
+            let VersionedItem { mut item, etag } = self.get_versioned::<T>(key.clone()).await?;
+            update(&mut item);
+
+            let new_key = item.try_get_key().map_err(Error::KeyBuildError)?;
+            if new_key != key {
+                return Err(Error::KeyChanged {
+                    bucket: self.bucket_name.clone(),
+                    key,
+                    new_key,
+                });
+            }
+
+            match self.put_if_match(item, etag).await {
+                Err(Error::PreconditionFailed { .. }) if attempt < max_attempts => {
+                    tokio::time::sleep(backoff.backoff(attempt as u32)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
     }
 
     pub async fn get_maybe_with_partial_keys<
-        T: KeyBuilder + TryFrom<S3Object, Error = impl std::fmt::Debug>,
+        T: KeyBuilder + TryFrom<S3Object, Error = impl Into<BoxError>>,
     >(
         &self,
         partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
     ) -> Result<Option<T>, Error> {
-        let key = T::build_key(partial_keys);
+        let key = T::try_build_key(partial_keys).map_err(Error::KeyBuildError)?;
+        self.get_maybe(key).await
+    }
+
+    pub async fn get_maybe_by_key<
+        T: HasTypedKey + TryFrom<S3Object, Error = impl Into<BoxError>>,
+    >(
+        &self,
+        key: T::TypedKey,
+    ) -> Result<Option<T>, Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
         self.get_maybe(key).await
     }
 
-    pub async fn get_maybe<T: TryFrom<S3Object, Error = impl std::fmt::Debug>>(
+    pub async fn get_maybe<T: TryFrom<S3Object, Error = impl Into<BoxError>>>(
         &self,
         key: String,
     ) -> Result<Option<T>, Error> {
-        let result = self
-            .client
-            .get_object()
-            .bucket(&self.bucket_name)
-            .key(&key)
-            .send()
-            .await;
-
-        let result = match result {
-            Ok(x) => x,
-            Err(e) => match e {
-                SdkError::ServiceError(e) if e.err().is_no_such_key() => {
-                    return Ok(None);
-                }
-                e => {
-                    return Err(Error::GetError(e));
-                }
-            },
+        let Some((object, _)) = self.get_s3_object(key).await? else {
+            return Ok(None);
+        };
+
+        self.decode(object).map(Some)
+    }
+
+    fn decode<T: TryFrom<S3Object, Error = impl Into<BoxError>>>(
+        &self,
+        object: S3Object,
+    ) -> Result<T, Error> {
+        let key = object.key.clone();
+        T::try_from(object).map_err(|e| Error::TryFromByteError {
+            key,
+            bucket: self.bucket_name.clone(),
+            source: e.into(),
+        })
+    }
+
+    async fn get_s3_object(&self, key: String) -> Result<Option<(S3Object, S3ObjectMeta)>, Error> {
+        let Some(result) = self.get_object(key.clone(), None, None).await? else {
+            return Ok(None);
         };
 
         let bytes = result
             .body
             .collect()
             .await
-            .map_err(|_| Error::ByteStreamCollectionError)?
+            .map_err(Error::ByteStreamCollectionError)?
             .into_bytes();
 
-        let s3_object = S3Object::new(bytes, key);
+        Ok(Some((S3Object::new(bytes, key), result.meta)))
+    }
+
+    async fn get_object(
+        &self,
+        key: String,
+        range: Option<ByteRange>,
+        if_match: Option<String>,
+    ) -> Result<Option<GetObjectResponse>, Error> {
+        self.store
+            .get_object(GetObjectRequest {
+                bucket: self.bucket_name.clone(),
+                key,
+                range,
+                if_match,
+            })
+            .await
+    }
+
+    pub async fn head_with_partial_keys<T: KeyBuilder>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+    ) -> Result<Option<S3ObjectMeta>, Error> {
+        let key = T::try_build_key(partial_keys).map_err(Error::KeyBuildError)?;
+        self.head(key).await
+    }
+
+    pub async fn head_by_key<T: HasTypedKey>(
+        &self,
+        key: T::TypedKey,
+    ) -> Result<Option<S3ObjectMeta>, Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
+        self.head(key).await
+    }
+
+    pub async fn head(&self, key: String) -> Result<Option<S3ObjectMeta>, Error> {
+        self.store.head_object(&self.bucket_name, &key).await
+    }
+
+    pub async fn exists_with_partial_keys<T: KeyBuilder>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+    ) -> Result<bool, Error> {
+        let key = T::try_build_key(partial_keys).map_err(Error::KeyBuildError)?;
+        self.exists(key).await
+    }
+
+    pub async fn exists_by_key<T: HasTypedKey>(&self, key: T::TypedKey) -> Result<bool, Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
+        self.exists(key).await
+    }
+
+    pub async fn exists(&self, key: String) -> Result<bool, Error> {
+        self.head(key).await.map(|x| x.is_some())
+    }
+
+    pub async fn list_keys<T: KeyBuilder>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+    ) -> Result<Vec<String>, Error> {
+        let prefix = T::try_build_prefix(partial_keys).map_err(Error::KeyBuildError)?;
+        self.list_keys_with_prefix(prefix).await
+    }
+
+    pub async fn list_keys_with_prefix(&self, prefix: String) -> Result<Vec<String>, Error> {
+        self.stream_keys(prefix).try_collect().await
+    }
+
+    pub async fn list<T: KeyBuilder + TryFrom<S3Object, Error = impl Into<BoxError>>>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+    ) -> Result<Vec<T>, Error> {
+        let prefix = T::try_build_prefix(partial_keys).map_err(Error::KeyBuildError)?;
+        self.list_with_prefix(prefix).await
+    }
+
+    pub async fn list_with_prefix<T: TryFrom<S3Object, Error = impl Into<BoxError>>>(
+        &self,
+        prefix: String,
+    ) -> Result<Vec<T>, Error> {
+        let mut items = vec![];
+        for key in self.list_keys_with_prefix(prefix).await? {
+            items.push(self.get(key).await?);
+        }
+
+        Ok(items)
+    }
+
+    pub fn stream_keys(
+        &self,
+        prefix: String,
+    ) -> impl Stream<Item = Result<String, Error>> + use<'a> {
+        let store = self.store;
+        let bucket_name = self.bucket_name.clone();
 
-        T::try_from(s3_object)
-            .map_err(|e| {
-                eprintln!("{:?}", e);
-                Error::TryFromByteError
+        // The state is `None` once the last page was fetched.
+        stream::try_unfold(Some(None), move |continuation_token| {
+            let bucket_name = bucket_name.clone();
+            let prefix = prefix.clone();
+            async move {
+                let Some(continuation_token) = continuation_token else {
+                    return Ok(None);
+                };
+                let page = store
+                    .list_objects(&bucket_name, &prefix, continuation_token)
+                    .await?;
+                let next = page.next_continuation_token.map(Some);
+                Ok(Some((page.keys, next)))
+            }
+        })
+        .map_ok(|keys| stream::iter(keys.into_iter().map(Ok)))
+        .try_flatten()
+    }
+
+    pub fn stream_items_with_partial_keys<T>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+    ) -> impl Stream<Item = Result<T, Error>> + use<'a, T>
+    where
+        T: KeyBuilder + TryFrom<S3Object>,
+        T::Error: Into<BoxError>,
+    {
+        match T::try_build_prefix(partial_keys) {
+            Ok(prefix) => self.stream_items(prefix).left_stream(),
+            Err(e) => stream::iter([Err(Error::KeyBuildError(e))]).right_stream(),
+        }
+    }
+
+    pub fn stream_items<T>(
+        &self,
+        prefix: String,
+    ) -> impl Stream<Item = Result<T, Error>> + use<'a, T>
+    where
+        T: TryFrom<S3Object>,
+        T::Error: Into<BoxError>,
+    {
+        self.stream_items_with_concurrency(prefix, DEFAULT_STREAM_CONCURRENCY)
+    }
+
+    /// Lazily lists the objects under `prefix` and fetches up to `concurrency` bodies at a time.
+    /// Items are yielded in key order.
+    pub fn stream_items_with_concurrency<T>(
+        &self,
+        prefix: String,
+        concurrency: usize,
+    ) -> impl Stream<Item = Result<T, Error>> + use<'a, T>
+    where
+        T: TryFrom<S3Object>,
+        T::Error: Into<BoxError>,
+    {
+        let bucket = self.clone();
+        self.stream_keys(prefix)
+            .map(move |key| {
+                let bucket = bucket.clone();
+                async move { bucket.get(key?).await }
             })
-            .map(|x| Some(x))
+            .buffered(concurrency.max(1))
     }
 
     pub async fn delete_with_partial_keys<T: KeyBuilder>(
         &self,
         partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
     ) -> Result<(), Error> {
-        let key = T::build_key(partial_keys);
+        let key = T::try_build_key(partial_keys).map_err(Error::KeyBuildError)?;
+        self.delete(key).await
+    }
+
+    pub async fn delete_by_key<T: HasTypedKey>(&self, key: T::TypedKey) -> Result<(), Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
         self.delete(key).await
     }
 
     pub async fn delete(&self, key: String) -> Result<(), Error> {
-        self.client
-            .delete_object()
-            .bucket(&self.bucket_name)
-            .key(key)
-            .send()
+        self.store.delete_object(&self.bucket_name, &key).await
+    }
+
+    /// Copies `key` to `destination_key` within this bucket, keeping content type and metadata.
+    pub async fn copy(&self, key: String, destination_key: String) -> Result<(), Error> {
+        self.copy_to(key, self.bucket_name.clone(), destination_key, None)
+            .await
+    }
+
+    /// Copies `key` to `destination_key` within this bucket, replacing content type and metadata.
+    pub async fn copy_with_attributes(
+        &self,
+        key: String,
+        destination_key: String,
+        attributes: ObjectAttributes,
+    ) -> Result<(), Error> {
+        self.copy_to(
+            key,
+            self.bucket_name.clone(),
+            destination_key,
+            Some(attributes),
+        )
+        .await
+    }
+
+    pub async fn copy_by_key<T: HasTypedKey>(
+        &self,
+        key: T::TypedKey,
+        destination: T::TypedKey,
+    ) -> Result<(), Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
+        let destination_key = destination.try_get_key().map_err(Error::KeyBuildError)?;
+        self.copy(key, destination_key).await
+    }
+
+    /// Copies `key` to `destination_key` in `destination_bucket`. Content type and metadata are
+    /// kept unless `attributes` is given.
+    pub async fn copy_to(
+        &self,
+        key: String,
+        destination_bucket: String,
+        destination_key: String,
+        attributes: Option<ObjectAttributes>,
+    ) -> Result<(), Error> {
+        self.store
+            .copy_object(CopyObjectRequest {
+                source_bucket: self.bucket_name.clone(),
+                source_key: key,
+                bucket: destination_bucket,
+                key: destination_key,
+                attributes,
+                ..Default::default()
+            })
+            .await
+    }
+
+    /// Copies `key` to `destination_key` and deletes the original once the copy succeeded.
+    pub async fn move_item(&self, key: String, destination_key: String) -> Result<(), Error> {
+        if key == destination_key {
+            return Ok(());
+        }
+        self.copy(key.clone(), destination_key).await?;
+        self.delete(key).await
+    }
+
+    pub async fn move_by_key<T: HasTypedKey>(
+        &self,
+        key: T::TypedKey,
+        destination: T::TypedKey,
+    ) -> Result<(), Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
+        let destination_key = destination.try_get_key().map_err(Error::KeyBuildError)?;
+        self.move_item(key, destination_key).await
+    }
+
+    /// Deletes all `keys`. Keys which could not be deleted are listed in the report's `failures`.
+    pub async fn delete_many(&self, keys: Vec<String>) -> Result<DeleteReport, Error> {
+        if keys.is_empty() {
+            return Ok(DeleteReport::default());
+        }
+        let mut report = self
+            .store
+            .delete_objects(&self.bucket_name, keys.clone())
+            .await?;
+        report.matched = keys;
+        Ok(report)
+    }
+
+    /// Deletes every key starting with the prefix built from `partial_keys`. With `dry_run` nothing
+    /// is deleted and only the report's `matched` keys are filled.
+    pub async fn delete_prefix<T: KeyBuilder>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        dry_run: bool,
+    ) -> Result<DeleteReport, Error> {
+        let prefix = T::try_build_prefix(partial_keys).map_err(Error::KeyBuildError)?;
+        self.delete_with_prefix(prefix, dry_run).await
+    }
+
+    pub async fn delete_with_prefix(
+        &self,
+        prefix: String,
+        dry_run: bool,
+    ) -> Result<DeleteReport, Error> {
+        if prefix.is_empty() {
+            return Err(Error::Other(String::from(
+                "Refusing to delete with an empty prefix, which matches the whole bucket.",
+            )));
+        }
+
+        let keys = self.list_keys_with_prefix(prefix).await?;
+        if dry_run {
+            return Ok(DeleteReport {
+                matched: keys,
+                ..Default::default()
+            });
+        }
+        self.delete_many(keys).await
+    }
+
+    pub async fn generate_presigned_url_with_partial_keys<T: KeyBuilder>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        lifetime_duration: Duration,
+    ) -> Result<String, Error> {
+        self.generate_presigned_url_with_partial_keys_with_options::<T>(
+            partial_keys,
+            lifetime_duration,
+            PresignedUrlOptions::default(),
+        )
+        .await
+    }
+
+    pub async fn generate_presigned_url_with_partial_keys_with_options<T: KeyBuilder>(
+        &self,
+        partial_keys: Vec<Box<dyn std::fmt::Display + Send>>,
+        lifetime_duration: Duration,
+        options: PresignedUrlOptions,
+    ) -> Result<String, Error> {
+        let key = T::try_build_key(partial_keys).map_err(Error::KeyBuildError)?;
+        self.generate_presigned_url_with_options(key, lifetime_duration, options)
+            .await
+    }
+
+    pub async fn generate_presigned_url_by_key<T: HasTypedKey>(
+        &self,
+        key: T::TypedKey,
+        lifetime_duration: Duration,
+    ) -> Result<String, Error> {
+        self.generate_presigned_url_by_key_with_options::<T>(
+            key,
+            lifetime_duration,
+            PresignedUrlOptions::default(),
+        )
+        .await
+    }
+
+    pub async fn generate_presigned_url_by_key_with_options<T: HasTypedKey>(
+        &self,
+        key: T::TypedKey,
+        lifetime_duration: Duration,
+        options: PresignedUrlOptions,
+    ) -> Result<String, Error> {
+        let key = key.try_get_key().map_err(Error::KeyBuildError)?;
+        self.generate_presigned_url_with_options(key, lifetime_duration, options)
             .await
-            .map(|_| ())
-            .map_err(|e| Error::DeleteError(e))
     }
 
     pub async fn generate_presigned_url(
@@ -154,17 +694,44 @@ impl<'a> S3Bucket<'a> {
         key: String,
         lifetime_duration: Duration,
     ) -> Result<String, Error> {
-        let result = self
-            .client
-            .get_object()
-            .bucket(&self.bucket_name)
-            .key(key)
-            .presigned(
-                PresigningConfig::expires_in(lifetime_duration)
-                    .map_err(|e| Error::PresigningConfigError(e))?,
-            )
+        self.generate_presigned_url_with_options(
+            key,
+            lifetime_duration,
+            PresignedUrlOptions::default(),
+        )
+        .await
+    }
+
+    /// Presigns a GET of `key` with response header overrides, e.g. a download filename.
+    pub async fn generate_presigned_url_with_options(
+        &self,
+        key: String,
+        lifetime_duration: Duration,
+        options: PresignedUrlOptions,
+    ) -> Result<String, Error> {
+        self.store
+            .presign_get_object(&self.bucket_name, &key, lifetime_duration, options)
+            .await
+    }
+
+    /// Presigns a PUT of `key`. The upload must send `content_type` as its `Content-Type` header.
+    pub async fn generate_presigned_put_url(
+        &self,
+        key: String,
+        content_type: Option<String>,
+        lifetime_duration: Duration,
+    ) -> Result<String, Error> {
+        self.store
+            .presign_put_object(&self.bucket_name, &key, content_type, lifetime_duration)
+            .await
+    }
+
+    pub async fn generate_presigned_post(
+        &self,
+        policy: PostPolicy,
+    ) -> Result<PresignedPost, Error> {
+        self.store
+            .presign_post_object(&self.bucket_name, policy)
             .await
-            .map_err(|e| Error::GetError(e))?;
-        Ok(result.uri().to_string())
     }
 }
@@ -0,0 +1,141 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use futures::{StreamExt, TryStreamExt, stream};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncWrite, AsyncWriteExt},
+};
+
+use crate::{byte_range::ByteRange, download_options::DownloadOptions, error::Error};
+
+use super::S3Bucket;
+
+impl<'a> S3Bucket<'a> {
+    /// Streams the object into `writer` and returns the number of bytes written.
+    pub async fn get_to_writer<W: AsyncWrite + Unpin + Send>(
+        &self,
+        key: String,
+        writer: &mut W,
+    ) -> Result<u64, Error> {
+        let Some(mut result) = self.get_object(key.clone(), None, None).await? else {
+            return Err(Error::NotFound {
+                bucket: self.bucket_name.clone(),
+                key,
+            });
+        };
+
+        let mut written = 0;
+        while let Some(bytes) = result.body.next().await {
+            let bytes = bytes.map_err(Error::ByteStreamCollectionError)?;
+            writer.write_all(&bytes).await.map_err(Error::IoError)?;
+            written += bytes.len() as u64;
+        }
+        writer.flush().await.map_err(Error::IoError)?;
+
+        verify_size(&key, result.meta.size, written)?;
+        Ok(written)
+    }
+
+    /// Downloads the object into `path` through a temporary `<path>.<random>.part` file, which is
+    /// removed if the download fails. With `options.concurrency` above 1 the object is fetched with
+    /// parallel ranged GETs.
+    pub async fn download_to_path(
+        &self,
+        key: String,
+        path: impl AsRef<Path>,
+        options: DownloadOptions,
+    ) -> Result<u64, Error> {
+        let path = path.as_ref();
+        let (part_path, file) = create_part_file(path).await?;
+
+        let result = self.download_to_file(key, file, options).await;
+        let result = match result {
+            Ok(x) => tokio::fs::rename(&part_path, path)
+                .await
+                .map(|_| x)
+                .map_err(Error::IoError),
+            Err(e) => Err(e),
+        };
+
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&part_path).await;
+        }
+
+        result
+    }
+
+    async fn download_to_file(
+        &self,
+        key: String,
+        mut file: File,
+        options: DownloadOptions,
+    ) -> Result<u64, Error> {
+        if options.concurrency <= 1 {
+            return self.get_to_writer(key, &mut file).await;
+        }
+
+        let Some(meta) = self.head(key.clone()).await? else {
+            return Err(Error::NotFound {
+                bucket: self.bucket_name.clone(),
+                key,
+            });
+        };
+
+        let part_size = options.part_size.max(1);
+        let ranges = (0..meta.size)
+            .step_by(part_size as usize)
+            .map(|start| (start, (start + part_size).min(meta.size) - 1));
+
+        let mut parts = stream::iter(ranges)
+            .map(|(start, end)| {
+                let range = ByteRange::Inclusive { start, end };
+                self.get_range_if_match(key.clone(), range, meta.etag.clone())
+            })
+            .buffered(options.concurrency);
+
+        let mut written = 0;
+        while let Some(part) = parts.try_next().await? {
+            file.write_all(&part.bytes).await.map_err(Error::IoError)?;
+            written += part.bytes.len() as u64;
+        }
+        file.flush().await.map_err(Error::IoError)?;
+
+        verify_size(&key, meta.size, written)?;
+        Ok(written)
+    }
+}
+
+/// Creates a new temp file next to `path`. The random suffix keeps concurrent downloads to the same
+/// destination apart, and existing files are never reused.
+async fn create_part_file(path: &Path) -> Result<(PathBuf, File), Error> {
+    loop {
+        let mut part_path = PathBuf::from(path).into_os_string();
+        part_path.push(format!(".{:016x}.part", fastrand::u64(..)));
+        let part_path = PathBuf::from(part_path);
+
+        let result = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&part_path)
+            .await;
+        match result {
+            Ok(file) => return Ok((part_path, file)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(Error::IoError(e)),
+        }
+    }
+}
+
+fn verify_size(key: &str, expected: u64, actual: u64) -> Result<(), Error> {
+    if expected != actual {
+        return Err(Error::SizeMismatch {
+            key: key.to_string(),
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use std::path::Path;
+
+use bytes::BytesMut;
+use futures::{StreamExt, stream::FuturesUnordered};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{
+    error::Error,
+    object_store::{ObjectAttributes, PutObjectRequest, UploadedPart},
+    upload_options::{MAX_PARTS, UploadOptions},
+};
+
+use super::S3Bucket;
+
+impl<'a> S3Bucket<'a> {
+    pub async fn put_file(
+        &self,
+        key: String,
+        content_type: String,
+        path: impl AsRef<Path>,
+        options: UploadOptions,
+    ) -> Result<(), Error> {
+        let file = tokio::fs::File::open(path).await.map_err(Error::IoError)?;
+        let size = file.metadata().await.map_err(Error::IoError)?.len();
+        let options = options.with_part_size(options.part_size_for(Some(size)));
+        self.put_reader(key, content_type, file, options).await
+    }
+
+    /// Uploads everything read from `reader`. Uploads reaching `options.multipart_threshold`
+    /// switch to a multipart upload, which is aborted if any part fails.
+    pub async fn put_reader<R: AsyncRead + Unpin + Send>(
+        &self,
+        key: String,
+        content_type: String,
+        mut reader: R,
+        options: UploadOptions,
+    ) -> Result<(), Error> {
+        let mut buffer = BytesMut::new();
+        let threshold = options.multipart_threshold.max(1);
+        let is_complete = fill_buffer(&mut reader, &mut buffer, threshold).await?;
+        let attributes = ObjectAttributes {
+            content_type: Some(content_type),
+            ..Default::default()
+        };
+        if is_complete {
+            return self
+                .store
+                .put_object(PutObjectRequest {
+                    bucket: self.bucket_name.clone(),
+                    key,
+                    body: buffer.freeze(),
+                    attributes,
+                    ..Default::default()
+                })
+                .await
+                .map(|_| ());
+        }
+
+        let upload_id = self
+            .store
+            .create_multipart_upload(&self.bucket_name, &key, attributes)
+            .await?;
+
+        let result = match self
+            .upload_parts(&key, &upload_id, reader, buffer, options)
+            .await
+        {
+            Ok(parts) => {
+                self.store
+                    .complete_multipart_upload(&self.bucket_name, &key, &upload_id, parts)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+
+        if result.is_err() {
+            let _ = self
+                .store
+                .abort_multipart_upload(&self.bucket_name, &key, &upload_id)
+                .await;
+        }
+
+        result
+    }
+
+    async fn upload_parts<R: AsyncRead + Unpin + Send>(
+        &self,
+        key: &str,
+        upload_id: &str,
+        mut reader: R,
+        mut buffer: BytesMut,
+        options: UploadOptions,
+    ) -> Result<Vec<UploadedPart>, Error> {
+        let part_size = options.part_size_for(None);
+        let concurrency = options.concurrency.max(1);
+        let mut in_flight = FuturesUnordered::new();
+        let mut parts = vec![];
+        let mut part_number = 0;
+        let mut is_complete = false;
+
+        loop {
+            while !is_complete && in_flight.len() < concurrency {
+                if (buffer.len() as u64) < part_size {
+                    is_complete = fill_buffer(&mut reader, &mut buffer, part_size).await?;
+                }
+                if buffer.is_empty() {
+                    break;
+                }
+
+                part_number += 1;
+                if part_number as u64 > MAX_PARTS {
+                    return Err(Error::Other(format!(
+                        "Upload exceeds {MAX_PARTS} parts, increase the part size."
+                    )));
+                }
+
+                let length = buffer.len().min(part_size as usize);
+                let part = buffer.split_to(length).freeze();
+                in_flight.push(self.store.upload_part(
+                    &self.bucket_name,
+                    key,
+                    upload_id,
+                    part_number,
+                    part,
+                ));
+            }
+
+            match in_flight.next().await {
+                Some(part) => parts.push(part?),
+                None => break,
+            }
+        }
+
+        parts.sort_by_key(|x| x.part_number);
+        Ok(parts)
+    }
+}
+
+/// Reads from `reader` until `buffer` holds `size` bytes. Returns `true` once the reader is exhausted.
+async fn fill_buffer<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buffer: &mut BytesMut,
+    size: u64,
+) -> Result<bool, Error> {
+    buffer.reserve((size as usize).saturating_sub(buffer.len()));
+    while (buffer.len() as u64) < size {
+        let remaining = size as usize - buffer.len();
+        let mut chunk = reader.take(remaining as u64);
+        let read = chunk.read_buf(buffer).await.map_err(Error::IoError)?;
+        if read == 0 {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
@@ -0,0 +1,101 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use aws_sdk_s3::Client;
+
+use crate::{
+    error::Error,
+    object_store::{
+        AwsObjectStore, ConfiguredObjectStore, ObjectAttributes, ObjectStore, RetryingObjectStore,
+    },
+    retry_policy::RetryPolicy,
+};
+
+use super::S3Context;
+
+#[derive(Debug, Default)]
+pub struct S3ContextBuilder {
+    store: Option<Arc<dyn ObjectStore>>,
+    retry_policy: Option<RetryPolicy>,
+    default_bucket: Option<String>,
+    key_prefix: String,
+    bucket_names: HashMap<String, String>,
+    default_attributes: ObjectAttributes,
+    timeout: Option<Duration>,
+}
+
+impl S3ContextBuilder {
+    pub fn with_client(self, client: Client) -> Self {
+        self.with_store(AwsObjectStore::new(client))
+    }
+
+    pub fn with_store(mut self, store: impl ObjectStore + 'static) -> Self {
+        self.store = Some(Arc::new(store));
+        self
+    }
+
+    /// Retries every operation of the context according to `retry_policy`.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    pub fn with_default_bucket(mut self, bucket_name: String) -> Self {
+        self.default_bucket = Some(bucket_name);
+        self
+    }
+
+    /// Prepended to every key, e.g. `dev/` to keep environments apart in a shared bucket.
+    pub fn with_key_prefix(mut self, key_prefix: String) -> Self {
+        self.key_prefix = key_prefix;
+        self
+    }
+
+    /// Stores everything addressed to `bucket_name` in `actual_bucket_name` instead.
+    pub fn with_bucket_name(mut self, bucket_name: String, actual_bucket_name: String) -> Self {
+        self.bucket_names.insert(bucket_name, actual_bucket_name);
+        self
+    }
+
+    pub fn with_storage_class(mut self, storage_class: String) -> Self {
+        self.default_attributes.storage_class = Some(storage_class);
+        self
+    }
+
+    pub fn with_server_side_encryption(mut self, server_side_encryption: String) -> Self {
+        self.default_attributes.server_side_encryption = Some(server_side_encryption);
+        self
+    }
+
+    pub fn with_sse_kms_key_id(mut self, sse_kms_key_id: String) -> Self {
+        self.default_attributes.sse_kms_key_id = Some(sse_kms_key_id);
+        self
+    }
+
+    /// Fails requests, each attempt separately when retrying, that take longer than `timeout`.
+    /// For gets this bounds the request until the response arrives, not the streaming of its body
+    /// afterwards; large downloads should bound their reads themselves. Copies and batch deletes
+    /// send a request per part or chunk and aren't bounded; configure the timeouts of the SDK
+    /// client to bound each of those requests.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(self) -> Result<S3Context, Error> {
+        let mut store = self.store.ok_or(Error::MissingObjectStore)?;
+        store = Arc::new(ConfiguredObjectStore {
+            inner: store,
+            key_prefix: self.key_prefix,
+            bucket_names: self.bucket_names,
+            default_attributes: self.default_attributes,
+            timeout: self.timeout,
+        });
+        if let Some(retry_policy) = self.retry_policy {
+            store = Arc::new(RetryingObjectStore::new(store, retry_policy));
+        }
+        Ok(S3Context {
+            store,
+            default_bucket: self.default_bucket,
+        })
+    }
+}
@@ -8,4 +8,36 @@ impl S3Object {
     pub fn new(bytes: bytes::Bytes, key: String) -> Self {
         Self { bytes, key }
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone)]
+pub struct S3ObjectRange {
+    pub bytes: bytes::Bytes,
+    pub key: String,
+    /// Offset of the first returned byte.
+    pub start: u64,
+    /// Size of the whole object.
+    pub total_size: u64,
+}
+
+impl S3ObjectRange {
+    /// Builds the range from a `Content-Range` header such as `bytes 0-99/1234`. Without the
+    /// header the whole object was returned.
+    pub fn new(bytes: bytes::Bytes, key: String, content_range: Option<&str>) -> Self {
+        let parsed = content_range
+            .and_then(|x| x.strip_prefix("bytes "))
+            .and_then(|x| x.split_once('/'))
+            .and_then(|(range, total)| {
+                let (start, _) = range.split_once('-')?;
+                Some((start.parse().ok()?, total.parse().ok()?))
+            });
+
+        let (start, total_size) = parsed.unwrap_or((0, bytes.len() as u64));
+        Self {
+            bytes,
+            key,
+            start,
+            total_size,
+        }
+    }
+}
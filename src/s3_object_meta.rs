@@ -0,0 +1,30 @@
+use std::collections::HashMap;
+
+use aws_sdk_s3::{operation::head_object::HeadObjectOutput, primitives::DateTime};
+
+#[derive(Debug, Clone)]
+pub struct S3ObjectMeta {
+    pub key: String,
+    pub size: u64,
+    pub etag: Option<String>,
+    pub content_type: Option<String>,
+    pub last_modified: Option<DateTime>,
+    pub metadata: HashMap<String, String>,
+    pub storage_class: Option<String>,
+    pub version_id: Option<String>,
+}
+
+impl S3ObjectMeta {
+    pub fn from_head_object_output(key: String, output: HeadObjectOutput) -> Self {
+        Self {
+            key,
+            size: output.content_length.unwrap_or_default().max(0) as u64,
+            etag: output.e_tag,
+            content_type: output.content_type,
+            last_modified: output.last_modified,
+            metadata: output.metadata.unwrap_or_default(),
+            storage_class: output.storage_class.map(|x| x.as_str().to_string()),
+            version_id: output.version_id,
+        }
+    }
+}
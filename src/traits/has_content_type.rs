@@ -1,3 +1,8 @@
 pub trait HasContentType {
     fn get_content_type() -> String;
-}
\ No newline at end of file
+
+    /// Content type of this particular item, e.g. of one variant of an enum item.
+    fn content_type(&self) -> String {
+        Self::get_content_type()
+    }
+}
@@ -1,3 +1,13 @@
+use crate::error::KeyBuildError;
+
 pub trait HasKey {
+    /// Uses values rejected by the segment policy of their placeholder as given, see
+    /// `try_get_key`.
     fn get_key(&self) -> String;
-}
\ No newline at end of file
+
+    /// Like `get_key`, but fails instead of building a key from values rejected by the segment
+    /// policy of their placeholder.
+    fn try_get_key(&self) -> Result<String, KeyBuildError> {
+        Ok(self.get_key())
+    }
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use crate::{
+    S3Context,
+    error::Error,
+    presigned_url_options::PresignedUrlOptions,
+    traits::{has_bucket_name::HasBucketName, has_key::HasKey},
+};
+
+/// Presigned GET urls for an item, e.g. `item.presigned_url(&s3_context, duration)`.
+pub trait HasPresignedUrl {
+    fn presigned_url(
+        &self,
+        context: &S3Context,
+        lifetime_duration: Duration,
+    ) -> impl Future<Output = Result<String, Error>> + Send;
+
+    fn presigned_url_with_options(
+        &self,
+        context: &S3Context,
+        lifetime_duration: Duration,
+        options: PresignedUrlOptions,
+    ) -> impl Future<Output = Result<String, Error>> + Send;
+}
+
+impl<T: HasKey + HasBucketName> HasPresignedUrl for T {
+    fn presigned_url(
+        &self,
+        context: &S3Context,
+        lifetime_duration: Duration,
+    ) -> impl Future<Output = Result<String, Error>> + Send {
+        let key = self.try_get_key().map_err(Error::KeyBuildError);
+        async move {
+            context
+                .generate_presigned_url::<T>(key?, lifetime_duration)
+                .await
+        }
+    }
+
+    fn presigned_url_with_options(
+        &self,
+        context: &S3Context,
+        lifetime_duration: Duration,
+        options: PresignedUrlOptions,
+    ) -> impl Future<Output = Result<String, Error>> + Send {
+        let key = self.try_get_key().map_err(Error::KeyBuildError);
+        async move {
+            context
+                .generate_presigned_url_with_options::<T>(key?, lifetime_duration, options)
+                .await
+        }
+    }
+}
@@ -0,0 +1,5 @@
+use super::has_key::HasKey;
+
+pub trait HasTypedKey {
+    type TypedKey: HasKey;
+}
@@ -1,3 +1,180 @@
+use std::fmt::Display;
+
+use crate::error::KeyBuildError;
+
+/// Longest key S3 accepts, in bytes.
+pub const MAX_KEY_LENGTH: usize = 1024;
+
 pub trait KeyBuilder {
-    fn build_key(value: Vec<Box<dyn std::fmt::Display + Send>>) -> String;
+    /// Uses values rejected by the segment policy of their placeholder as given, see
+    /// `try_build_key`.
+    fn build_key(value: Vec<Box<dyn Display + Send + '_>>) -> String;
+
+    /// Like `build_key`, but fails on values rejected by the segment policy of their placeholder
+    /// and on keys S3 wouldn't accept.
+    fn try_build_key(value: Vec<Box<dyn Display + Send + '_>>) -> Result<String, KeyBuildError> {
+        Ok(Self::build_key(value))
+    }
+
+    /// Builds the longest static prefix of the key that can be derived from the given partial keys.
+    /// Everything from the first placeholder that was not provided onwards is dropped.
+    fn build_prefix(value: Vec<Box<dyn Display + Send + '_>>) -> String {
+        let key = Self::build_key(value);
+        match key.find("{}") {
+            Some(index) => key[..index].to_string(),
+            None => key,
+        }
+    }
+
+    fn try_build_prefix(value: Vec<Box<dyn Display + Send + '_>>) -> Result<String, KeyBuildError> {
+        let key = Self::try_build_key(value)?;
+        Ok(match key.find("{}") {
+            Some(index) => key[..index].to_string(),
+            None => key,
+        })
+    }
+}
+
+/// How the value of a placeholder is made safe to use in a key, set with
+/// `#[s3_item_prop(segment = "...")]` on the field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SegmentPolicy {
+    /// Values that would change the structure of the key are rejected: empty values, `.` and `..`,
+    /// and values containing `/`, `{`, `}` or control characters.
+    Reject,
+    /// Everything but ASCII letters, digits, `-`, `_`, `.` and `~` is percent-encoded.
+    PercentEncode,
+    /// Like `Reject`, but `/` is allowed between path segments, e.g. for dates such as `2024/05/07`.
+    /// The default, so fields holding paths keep working.
+    #[default]
+    AllowSlash,
+}
+
+impl SegmentPolicy {
+    /// The value to put in the key for `value` without checking it.
+    pub fn render(self, value: &str) -> String {
+        match self {
+            SegmentPolicy::PercentEncode if value == "." || value == ".." => {
+                "%2E".repeat(value.len())
+            }
+            SegmentPolicy::PercentEncode => percent_encode(value),
+            _ => value.to_string(),
+        }
+    }
+
+    /// The value to put in the key for `value`, or why it can't be used.
+    pub fn apply(self, field: &str, value: &str) -> Result<String, KeyBuildError> {
+        let invalid = |reason: &str| KeyBuildError::InvalidSegment {
+            field: field.to_string(),
+            value: value.to_string(),
+            reason: reason.to_string(),
+        };
+
+        if self == SegmentPolicy::PercentEncode {
+            return Ok(self.render(value));
+        }
+
+        if value.chars().any(char::is_control) {
+            return Err(invalid("it contains control characters"));
+        }
+        if value.contains(['{', '}']) {
+            return Err(invalid("it contains `{` or `}`"));
+        }
+        let segments: Vec<&str> = match self {
+            SegmentPolicy::AllowSlash => value.split('/').collect(),
+            _ if value.contains('/') => return Err(invalid("it contains `/`")),
+            _ => vec![value],
+        };
+        for segment in segments {
+            if segment.is_empty() {
+                return Err(invalid("empty path segment"));
+            }
+            if segment == "." || segment == ".." {
+                return Err(invalid("`.` and `..` are not allowed as path segments"));
+            }
+        }
+        Ok(self.render(value))
+    }
+}
+
+/// A placeholder of a key template, as passed to `fill_key_template`.
+#[derive(Debug, Clone, Copy)]
+pub struct KeySegment {
+    pub field: &'static str,
+    pub policy: SegmentPolicy,
+}
+
+/// Fills the `{}` placeholders of `template` with `value`, in order. Values of percent-encoded
+/// segments are encoded, every other value is used as given, even if its policy rejects it.
+/// Placeholders without a value are kept as they are.
+pub fn fill_key_template(
+    template: &str,
+    segments: &[KeySegment],
+    value: Vec<Box<dyn Display + Send + '_>>,
+) -> String {
+    let value = value.iter().enumerate().map(|(index, x)| {
+        let policy = segments.get(index).map(|x| x.policy).unwrap_or_default();
+        policy.render(&x.to_string())
+    });
+    fill(template, value)
+}
+
+/// Like `fill_key_template`, but fails on rejected values and on keys, or prefixes for partial keys,
+/// that S3 wouldn't accept.
+pub fn try_fill_key_template(
+    template: &str,
+    segments: &[KeySegment],
+    value: Vec<Box<dyn Display + Send + '_>>,
+) -> Result<String, KeyBuildError> {
+    let value = value
+        .iter()
+        .enumerate()
+        .map(|(index, x)| {
+            let segment = segments.get(index);
+            let policy = segment.map(|x| x.policy).unwrap_or_default();
+            policy.apply(segment.map_or("", |x| x.field), &x.to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let key = fill(template, value.into_iter());
+
+    let prefix = match key.find("{}") {
+        Some(index) => &key[..index],
+        None if key.is_empty() => return Err(KeyBuildError::Empty),
+        None => &key,
+    };
+    if prefix.len() > MAX_KEY_LENGTH {
+        return Err(KeyBuildError::TooLong {
+            length: prefix.len(),
+            key: prefix.to_string(),
+            max_length: MAX_KEY_LENGTH,
+        });
+    }
+    Ok(key)
+}
+
+/// Interleaves the literal parts of `template` with `value`, so values containing `{}` can't be
+/// mistaken for placeholders.
+fn fill(template: &str, mut value: impl Iterator<Item = String>) -> String {
+    let mut parts = template.split("{}");
+    let mut key = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        match value.next() {
+            Some(x) => key.push_str(&x),
+            None => key.push_str("{}"),
+        }
+        key.push_str(part);
+    }
+    key
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut output = String::new();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            output.push(byte as char);
+        } else {
+            output.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    output
 }
@@ -0,0 +1,131 @@
+use std::str::FromStr;
+
+use crate::error::Error;
+
+pub trait KeyParser {
+    type KeyParts;
+
+    fn parse_key(key: &str) -> Result<Self::KeyParts, Error>;
+}
+
+/// Matches `key` against a key template such as `posts/{post_id}/comments.json` and returns the
+/// raw value of every placeholder, in the order they appear in the template. Placeholders used
+/// more than once must have the same value each time.
+pub fn match_key_template<'k>(template: &str, key: &'k str) -> Result<Vec<&'k str>, Error> {
+    let mismatch = |reason: String| Error::KeyMismatch {
+        key: key.to_string(),
+        template: template.to_string(),
+        reason,
+    };
+
+    let mut literals = vec![];
+    let mut placeholders = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        literals.push(&rest[..start]);
+        placeholders.push(&rest[start + 1..start + end]);
+        rest = &rest[start + end + 1..];
+    }
+    literals.push(rest);
+
+    let prefix = literals[0];
+    if !key.starts_with(prefix) {
+        return Err(mismatch(format!("expected `{prefix}` at position 0")));
+    }
+
+    let mut position = prefix.len();
+    let mut values = vec![];
+    for (index, placeholder) in placeholders.iter().enumerate() {
+        let literal = literals[index + 1];
+        let remaining = &key[position..];
+        let is_last = index + 1 == placeholders.len();
+
+        let value = if is_last {
+            let Some(value) = remaining.strip_suffix(literal) else {
+                return Err(mismatch(format!(
+                    "expected `{literal}` after the value of `{placeholder}` at the end of the key"
+                )));
+            };
+            value
+        } else if literal.is_empty() {
+            return Err(mismatch(format!(
+                "placeholder `{placeholder}` is directly followed by another placeholder"
+            )));
+        } else {
+            let Some(end) = remaining.find(literal) else {
+                return Err(mismatch(format!(
+                    "expected `{literal}` after the value of `{placeholder}` at position {position}"
+                )));
+            };
+            &remaining[..end]
+        };
+
+        if value.is_empty() {
+            return Err(mismatch(format!(
+                "empty value for `{placeholder}` at position {position}"
+            )));
+        }
+
+        // A repeated placeholder must have the same value everywhere.
+        if let Some(first) = placeholders[..index].iter().position(|x| x == placeholder)
+            && values[first] != value
+        {
+            return Err(mismatch(format!(
+                "`{placeholder}` is `{}` and `{value}` at position {position}",
+                values[first]
+            )));
+        }
+
+        values.push(value);
+        position += value.len() + literal.len();
+    }
+
+    if placeholders.is_empty() && key != template {
+        return Err(mismatch(String::from(
+            "the key is not equal to the static key",
+        )));
+    }
+
+    Ok(values)
+}
+
+pub fn parse_key_segment<T: FromStr>(key: &str, field: &str, value: &str) -> Result<T, Error> {
+    value.parse::<T>().map_err(|_| Error::KeySegmentParseError {
+        key: key.to_string(),
+        field: field.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// Like `parse_key_segment`, for fields using `SegmentPolicy::PercentEncode`.
+pub fn parse_encoded_key_segment<T: FromStr>(
+    key: &str,
+    field: &str,
+    value: &str,
+) -> Result<T, Error> {
+    let decoded = percent_decode(value).ok_or_else(|| Error::KeySegmentParseError {
+        key: key.to_string(),
+        field: field.to_string(),
+        value: value.to_string(),
+    })?;
+    parse_key_segment(key, field, &decoded)
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = vec![];
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
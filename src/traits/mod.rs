@@ -1,5 +1,8 @@
 pub mod has_bucket_name;
 pub mod has_content_type;
 pub mod has_key;
+pub mod has_presigned_url;
 pub mod has_static_key;
+pub mod has_typed_key;
 pub mod key_builder;
+pub mod key_parser;
@@ -0,0 +1,48 @@
+pub const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+pub const MAX_PARTS: u64 = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct UploadOptions {
+    /// Uploads of at least this many bytes are sent as a multipart upload.
+    pub multipart_threshold: u64,
+    /// Size of every part except the last one. Values below 5 MiB are raised to 5 MiB.
+    pub part_size: u64,
+    /// Maximum number of parts uploaded at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for UploadOptions {
+    fn default() -> Self {
+        Self {
+            multipart_threshold: 16 * 1024 * 1024,
+            part_size: 8 * 1024 * 1024,
+            concurrency: 4,
+        }
+    }
+}
+
+impl UploadOptions {
+    pub fn with_multipart_threshold(mut self, multipart_threshold: u64) -> Self {
+        self.multipart_threshold = multipart_threshold;
+        self
+    }
+
+    pub fn with_part_size(mut self, part_size: u64) -> Self {
+        self.part_size = part_size;
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Part size to use for an upload of `total_size` bytes, keeping the part count within the S3 limit.
+    pub fn part_size_for(&self, total_size: Option<u64>) -> u64 {
+        let part_size = self.part_size.max(MIN_PART_SIZE);
+        match total_size {
+            Some(total_size) => part_size.max(total_size.div_ceil(MAX_PARTS)),
+            None => part_size,
+        }
+    }
+}
@@ -0,0 +1,12 @@
+/// An item together with the ETag it was read with, for use with conditional writes.
+#[derive(Debug, Clone)]
+pub struct VersionedItem<T> {
+    pub item: T,
+    pub etag: String,
+}
+
+impl<T> VersionedItem<T> {
+    pub fn new(item: T, etag: String) -> Self {
+        Self { item, etag }
+    }
+}
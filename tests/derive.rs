@@ -0,0 +1,456 @@
+use std::borrow::Cow;
+
+use s3_bucket::{
+    JsonItem, S3BucketItem,
+    bytes::Bytes,
+    error::{Error, KeyBuildError},
+    s3_object::S3Object,
+    traits::{
+        has_bucket_name::HasBucketName, has_content_type::HasContentType, has_key::HasKey,
+        has_typed_key::HasTypedKey, key_builder::KeyBuilder, key_parser::KeyParser,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+fn bucket() -> String {
+    String::from("bucket")
+}
+
+#[derive(S3BucketItem, Clone)]
+#[s3_item_prop(bucket = bucket())]
+#[s3_item_prop(key = "builds/{os}/{version}/{name}")]
+#[s3_item_prop(parse)]
+pub struct Build {
+    pub os: String,
+    pub version: u32,
+    pub name: String,
+}
+
+#[test]
+fn parses_built_keys() {
+    let build = Build {
+        os: String::from("linux"),
+        version: 3,
+        name: String::from("app.tar.gz"),
+    };
+    let key = build.get_key();
+    assert_eq!(key, "builds/linux/3/app.tar.gz");
+
+    let parts = Build::parse_key(&key).unwrap();
+    assert_eq!(parts.os, "linux");
+    assert_eq!(parts.version, 3);
+    assert_eq!(parts.name, "app.tar.gz");
+    assert_eq!(parts.get_key(), key);
+
+    let built = Build::build_key(vec![Box::new("linux"), Box::new(3), Box::new("app.tar.gz")]);
+    assert_eq!(built, key);
+    assert_eq!(
+        Build::build_prefix(vec![Box::new("linux")]),
+        "builds/linux/"
+    );
+    assert_eq!(Build::get_bucket_name(), "bucket");
+}
+
+#[test]
+fn rejects_keys_not_matching_the_template() {
+    assert!(Build::parse_key("builds/linux/3").is_err());
+    assert!(Build::parse_key("other/linux/3/app").is_err());
+
+    let e = Build::parse_key("builds/linux/three/app").unwrap_err();
+    assert!(matches!(e, Error::KeySegmentParseError { .. }), "{e}");
+}
+
+#[derive(S3BucketItem, Clone)]
+#[s3_item_prop(bucket = bucket())]
+#[s3_item_prop(key = "pairs/{id}/{id}.json")]
+#[s3_item_prop(parse)]
+pub struct Pair {
+    pub id: String,
+}
+
+#[test]
+fn checks_repeated_placeholders() {
+    assert_eq!(Pair::parse_key("pairs/a/a.json").unwrap().id, "a");
+    assert!(Pair::parse_key("pairs/a/b.json").is_err());
+}
+
+#[test]
+fn typed_keys_match_item_keys() {
+    let build = Build {
+        os: String::from("mac"),
+        version: 1,
+        name: String::from("app"),
+    };
+    let key: BuildKey = (&build).into();
+    assert_eq!(key.get_key(), build.get_key());
+    assert_eq!(key.try_get_key().unwrap(), build.get_key());
+    assert_eq!(format!("{key:?}"), "BuildKey(\"builds/mac/1/app\")");
+}
+
+#[derive(S3BucketItem, Clone)]
+#[s3_item_prop(bucket = bucket())]
+#[s3_item_prop(key = "config/defaults.json")]
+pub struct Defaults<T: Clone> {
+    pub values: Vec<T>,
+}
+
+#[test]
+fn static_keys_have_unit_typed_keys() {
+    let defaults = Defaults { values: vec![1u8] };
+    let key: <Defaults<u8> as HasTypedKey>::TypedKey = (&defaults).into();
+    assert_eq!(key.get_key(), "config/defaults.json");
+    assert_eq!(DefaultsKey.try_get_key().unwrap(), defaults.get_key());
+    assert_eq!(
+        format!("{:?}", DefaultsKey),
+        "DefaultsKey(\"config/defaults.json\")"
+    );
+}
+
+#[derive(Serialize, Deserialize, S3BucketItem, JsonItem, Clone)]
+#[s3_item_prop(bucket = bucket())]
+#[s3_item_prop(key = "versioned/{id}/{version}.json")]
+#[s3_item_prop(content_type = "application/json")]
+#[s3_item_prop(parse)]
+pub struct Versioned<T> {
+    pub id: String,
+    pub version: u64,
+    pub data: T,
+}
+
+#[derive(S3BucketItem, Clone)]
+#[s3_item_prop(bucket = bucket())]
+#[s3_item_prop(key = "by-part/{part}/{id}")]
+#[s3_item_prop(bound = "P: std::fmt::Display + std::str::FromStr + Clone + Send")]
+#[s3_item_prop(parse)]
+pub struct Envelope<P, D: Default>
+where
+    D: Clone,
+{
+    pub part: P,
+    pub id: u32,
+    pub data: D,
+}
+
+#[derive(S3BucketItem, Clone)]
+#[s3_item_prop(bucket = bucket())]
+#[s3_item_prop(key = "docs/{name}.txt")]
+#[s3_item_prop(parse)]
+pub struct Doc<'a> {
+    pub name: String,
+    pub body: Cow<'a, str>,
+}
+
+#[test]
+fn round_trips_generic_and_borrowed_items() {
+    let versioned = Versioned {
+        id: String::from("a"),
+        version: 3,
+        data: vec![1u8],
+    };
+    let key: <Versioned<Vec<u8>> as HasTypedKey>::TypedKey = (&versioned).into();
+    assert_eq!(key.get_key(), "versioned/a/3.json");
+    let parts = Versioned::<Vec<u8>>::parse_key(&versioned.get_key()).unwrap();
+    assert_eq!((parts.id.as_str(), parts.version), ("a", 3));
+    assert_eq!(Versioned::<()>::get_content_type(), "application/json");
+
+    let envelope = Envelope {
+        part: 5u16,
+        id: 1,
+        data: String::new(),
+    };
+    let key: EnvelopeKey<u16> = (&envelope).into();
+    assert_eq!(key.get_key(), "by-part/5/1");
+    let parts = Envelope::<u16, String>::parse_key("by-part/5/1").unwrap();
+    assert_eq!((parts.part, parts.id), (5, 1));
+
+    let body = String::from("text");
+    let doc = Doc {
+        name: String::from("notes"),
+        body: Cow::Borrowed(&body),
+    };
+    assert_eq!(doc.get_key(), "docs/notes.txt");
+    let key: DocKey = (&doc).into();
+    assert_eq!(Doc::parse_key(&key.get_key()).unwrap().name, "notes");
+}
+
+#[derive(Serialize, Deserialize, JsonItem, Debug, PartialEq)]
+#[serde(bound = "T: Serialize + serde::de::DeserializeOwned")]
+#[s3_item_prop(bound = "T: Serialize + serde::de::DeserializeOwned")]
+pub struct Tagged<T> {
+    pub tag: String,
+    pub data: Vec<T>,
+}
+
+#[test]
+fn round_trips_json_items_with_bounds() {
+    let tagged = Tagged {
+        tag: String::from("a"),
+        data: vec![1u8, 2],
+    };
+    let bytes: Bytes = tagged.try_into().unwrap();
+    let object = S3Object::new(bytes, String::from("tagged.json"));
+    let tagged = Tagged::<u8>::try_from(object).unwrap();
+    assert_eq!((tagged.tag.as_str(), tagged.data), ("a", vec![1, 2]));
+}
+
+#[derive(Serialize, Deserialize, S3BucketItem, JsonItem, Clone, Debug, PartialEq)]
+#[s3_item_prop(bucket = bucket())]
+#[s3_item_prop(content_type = "application/json")]
+#[s3_item_prop(parse)]
+pub enum Asset {
+    #[s3_item_prop(key = "images/{id}.png", content_type = "image/png")]
+    Image { id: u32, width: u32 },
+    #[s3_item_prop(key = "docs/{owner}/{name}.json")]
+    Document {
+        owner: String,
+        name: String,
+        text: String,
+    },
+    #[s3_item_prop(key = "manifest.json")]
+    Manifest,
+}
+
+#[test]
+fn round_trips_enum_items() {
+    let image = Asset::Image { id: 4, width: 2 };
+    assert_eq!(image.get_key(), "images/4.png");
+    assert_eq!(image.content_type(), "image/png");
+    assert!(matches!(
+        Asset::parse_key(&image.get_key()).unwrap(),
+        AssetKey::Image { id: 4 }
+    ));
+
+    let document = Asset::Document {
+        owner: String::from("o"),
+        name: String::from("n"),
+        text: String::from("t"),
+    };
+    assert_eq!(document.get_key(), "docs/o/n.json");
+    assert_eq!(document.content_type(), "application/json");
+    let AssetKey::Document { owner, name } = Asset::parse_key(&document.get_key()).unwrap() else {
+        panic!("expected a document key");
+    };
+    assert_eq!((owner.as_str(), name.as_str()), ("o", "n"));
+    let key: AssetKey = (&document).into();
+    assert_eq!(key.get_key(), "docs/o/n.json");
+
+    assert_eq!(Asset::Manifest.get_key(), "manifest.json");
+    assert!(matches!(
+        Asset::parse_key("manifest.json").unwrap(),
+        AssetKey::Manifest
+    ));
+
+    assert!(Asset::parse_key("images/x.png").is_err());
+    assert!(Asset::parse_key("other").is_err());
+}
+
+#[derive(S3BucketItem, Clone)]
+#[s3_item_prop(bucket = bucket())]
+#[s3_item_prop(key = "n/{n:05}/{name}")]
+#[s3_item_prop(parse)]
+pub struct Numbered {
+    pub n: u32,
+    pub name: String,
+}
+
+#[derive(S3BucketItem, Clone)]
+#[s3_item_prop(bucket = bucket())]
+#[s3_item_prop(key = "v/{version:>4}/{name|upper}/{title|slug}")]
+pub struct Formatted {
+    pub version: u32,
+    pub name: String,
+    pub title: String,
+}
+
+#[test]
+fn round_trips_zero_padded_integers() {
+    let numbered = Numbered {
+        n: 42,
+        name: String::from("x"),
+    };
+    assert_eq!(numbered.get_key(), "n/00042/x");
+    assert_eq!(Numbered::parse_key("n/00042/x").unwrap().n, 42);
+    assert_eq!(
+        Numbered::build_key(vec![Box::new(42), Box::new("x")]),
+        numbered.get_key()
+    );
+    assert_eq!(Numbered::build_prefix(vec![Box::new(7)]), "n/00007/");
+}
+
+#[test]
+fn applies_formats_and_transforms() {
+    let formatted = Formatted {
+        version: 7,
+        name: String::from("ab"),
+        title: String::from("Hello, World!"),
+    };
+    assert_eq!(formatted.get_key(), "v/   7/AB/hello-world");
+    assert_eq!(
+        Formatted::build_prefix(vec![Box::new(7), Box::new("ab")]),
+        "v/   7/AB/"
+    );
+}
+
+#[cfg(feature = "chrono")]
+#[derive(S3BucketItem, Clone)]
+#[s3_item_prop(bucket = bucket())]
+#[s3_item_prop(
+    key = "logs/{user_id|sha256[..2]}/{user_id}/{created_at:%Y/%m/%d}/{version:08}.json"
+)]
+pub struct Log {
+    pub user_id: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub version: u32,
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn formats_dates_in_keys_and_prefixes() {
+    let at = chrono::NaiveDate::from_ymd_opt(2024, 5, 7)
+        .unwrap()
+        .and_hms_opt(1, 2, 3)
+        .unwrap();
+    let log = Log {
+        user_id: String::from("u1"),
+        created_at: at,
+        version: 42,
+    };
+    let hash = &s3_bucket::key_format::sha256("u1")[..2];
+    assert_eq!(
+        log.get_key(),
+        format!("logs/{hash}/u1/2024/05/07/00000042.json")
+    );
+    assert_eq!(
+        Log::build_key(vec![
+            Box::new("u1"),
+            Box::new("u1"),
+            Box::new(at),
+            Box::new(42)
+        ]),
+        log.get_key()
+    );
+    assert_eq!(
+        Log::build_prefix(vec![Box::new("u1"), Box::new("u1"), Box::new(at)]),
+        format!("logs/{hash}/u1/2024/05/07/")
+    );
+    assert_eq!(
+        Log::build_prefix(vec![Box::new("u1"), Box::new("u1"), Box::new("2024/05")]),
+        format!("logs/{hash}/u1/2024/05/")
+    );
+}
+
+#[test]
+fn formats_signed_and_extended_years() {
+    use s3_bucket::key_format::{KeyDateParts, format_key_date_str};
+
+    assert_eq!(
+        format_key_date_str("-0004-12-31", "%Y/%m/%d/%j"),
+        "-0004/12/31/366"
+    );
+    assert_eq!(
+        format_key_date_str("+10000-01-02T03:04:05Z", "%Y/%H"),
+        "+10000/03"
+    );
+    assert_eq!(format_key_date_str("0999-01-01", "%Y-%y"), "0999-99");
+    let parts = KeyDateParts::parse("-12345-03-01 00:00:00").unwrap();
+    assert_eq!(KeyDateParts::parse(&parts.to_string()), Some(parts));
+    assert_eq!(parts.to_string(), "-12345-03-01 00:00:00");
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn formats_signed_years_of_chrono_dates() {
+    for (year, formatted) in [
+        (-10000, "-10000"),
+        (-1, "-0001"),
+        (999, "0999"),
+        (10000, "+10000"),
+    ] {
+        let date = chrono::NaiveDate::from_ymd_opt(year, 2, 28).unwrap();
+        assert_eq!(
+            s3_bucket::key_format::format_key_date_str(&date.to_string(), "%Y/%j"),
+            format!("{formatted}/059")
+        );
+    }
+}
+
+#[derive(S3BucketItem, Clone, Debug)]
+#[s3_item_prop(bucket = bucket())]
+#[s3_item_prop(key = "files/{owner}/{name}/{path}")]
+#[s3_item_prop(parse)]
+pub struct File {
+    pub owner: String,
+    #[s3_item_prop(segment = "percent_encode")]
+    pub name: String,
+    #[s3_item_prop(segment = "reject")]
+    pub path: String,
+}
+
+fn file() -> File {
+    File {
+        owner: String::from("me/team"),
+        name: String::from("a b/{}"),
+        path: String::from("report.txt"),
+    }
+}
+
+#[test]
+fn round_trips_encoded_segments() {
+    let file = file();
+    let key = file.try_get_key().unwrap();
+    assert_eq!(key, "files/me/team/a%20b%2F%7B%7D/report.txt");
+    assert_eq!(file.get_key(), key);
+
+    let parts = File::parse_key("files/me/a%20b%2F%7B%7D/report.txt").unwrap();
+    assert_eq!(parts.owner, "me");
+    assert_eq!(parts.name, "a b/{}");
+    assert_eq!(parts.path, "report.txt");
+    assert!(File::parse_key("files/me/a%2/report.txt").is_err());
+
+    assert_eq!(
+        File::try_build_prefix(vec![Box::new("me"), Box::new("n n")]).unwrap(),
+        "files/me/n%20n/"
+    );
+}
+
+#[test]
+fn rejects_invalid_segments() {
+    let nested = File {
+        path: String::from("a/b"),
+        ..file()
+    };
+    let e = nested.try_get_key().unwrap_err();
+    assert!(
+        matches!(e, KeyBuildError::InvalidSegment { ref field, .. } if field == "path"),
+        "{e}"
+    );
+    let key: FileKey = (&nested).into();
+    assert!(key.try_get_key().is_err());
+    // The infallible variants build the key like 0.2 did.
+    assert_eq!(nested.get_key(), "files/me/team/a%20b%2F%7B%7D/a/b");
+
+    for owner in ["a//b", "a/../b", "{}"] {
+        let file = File {
+            owner: String::from(owner),
+            ..file()
+        };
+        assert!(file.try_get_key().is_err(), "{owner}");
+    }
+
+    let long = File {
+        owner: "x".repeat(1100),
+        ..file()
+    };
+    assert!(matches!(
+        long.try_get_key(),
+        Err(KeyBuildError::TooLong { .. })
+    ));
+    assert!(File::try_build_prefix(vec![Box::new("m//e")]).is_err());
+    assert_eq!(File::build_prefix(vec![Box::new("")]), "files//");
+    let empty = File {
+        owner: String::new(),
+        ..file()
+    };
+    assert!(empty.try_get_key().is_err());
+    assert_eq!(empty.get_key(), "files//a%20b%2F%7B%7D/report.txt");
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use aws_sdk_s3::{
+    Client,
+    config::{BehaviorVersion, Credentials, Region},
+};
+use s3_bucket::{
+    S3BucketItem, S3Context, object_store::AwsObjectStore, post_policy::PostPolicy,
+    presigned_url_options::PresignedUrlOptions,
+};
+
+fn bucket() -> String {
+    String::from("bucket")
+}
+
+#[derive(S3BucketItem)]
+#[s3_item_prop(bucket = bucket())]
+#[s3_item_prop(key = "reports/{year}/{name}")]
+#[s3_item_prop(content_type = "application/pdf")]
+pub struct Report {
+    pub year: u16,
+    pub name: String,
+}
+
+const ENDPOINT: &str = "http://localhost:9000";
+
+fn credentials() -> Credentials {
+    Credentials::new("key", "secret", None, None, "test")
+}
+
+fn client(endpoint_url: Option<&str>) -> Client {
+    let mut config = aws_sdk_s3::Config::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(Region::new("eu-west-1"))
+        .credentials_provider(credentials())
+        .force_path_style(endpoint_url.is_some());
+    config.set_endpoint_url(endpoint_url.map(String::from));
+    Client::from_conf(config.build())
+}
+
+fn s3_context() -> S3Context {
+    let store = AwsObjectStore::new(client(Some(ENDPOINT)))
+        .with_credentials_provider(credentials())
+        .with_endpoint_url(ENDPOINT)
+        .with_force_path_style(true);
+    S3Context::with_store(store)
+}
+
+#[tokio::test]
+async fn presigns_typed_gets_with_options() {
+    let s3_context = s3_context();
+    let options = PresignedUrlOptions::default().with_attachment_filename("report.pdf");
+
+    let url = s3_context
+        .generate_presigned_url_by_key_with_options::<Report>(
+            ReportKey {
+                year: 2024,
+                name: String::from("q1"),
+            },
+            Duration::from_secs(60),
+            options.clone(),
+        )
+        .await
+        .unwrap();
+    assert!(url.starts_with("http://localhost:9000/bucket/reports/2024/q1?"));
+    assert!(url.contains("response-content-disposition=attachment"));
+
+    let url = s3_context
+        .generate_presigned_url_with_partial_keys_with_options::<Report>(
+            vec![Box::new(2024), Box::new("q1")],
+            Duration::from_secs(60),
+            options,
+        )
+        .await
+        .unwrap();
+    assert!(url.starts_with("http://localhost:9000/bucket/reports/2024/q1?"));
+    assert!(url.contains("response-content-disposition=attachment"));
+}
+
+#[tokio::test]
+async fn presigns_posts_to_the_bucket_url() {
+    let policy = || PostPolicy::new(Duration::from_secs(60));
+    let post = s3_context()
+        .generate_presigned_post_with_partial_keys::<Report>(vec![Box::new(2024)], policy())
+        .await
+        .unwrap();
+    assert_eq!(post.url, "http://localhost:9000/bucket");
+
+    let store = AwsObjectStore::new(client(None)).with_credentials_provider(credentials());
+    let post = S3Context::with_store(store)
+        .generate_presigned_post_with_partial_keys::<Report>(vec![Box::new(2024)], policy())
+        .await
+        .unwrap();
+    assert_eq!(post.url, "https://bucket.s3.eu-west-1.amazonaws.com");
+}
@@ -0,0 +1,10 @@
+use s3_bucket::S3BucketItem;
+
+#[derive(S3BucketItem)]
+#[s3_item_prop(bucket = String::from("bucket"))]
+#[s3_item_prop(key = "items/{created_at:%Y/%q}")]
+pub struct Item {
+    pub created_at: String,
+}
+
+fn main() {}
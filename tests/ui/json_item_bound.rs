@@ -0,0 +1,15 @@
+use s3_bucket::{JsonItem, s3_object::S3Object};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonItem)]
+#[s3_item_prop(bound = "T: Copy")]
+pub struct Wrapper<T> {
+    pub data: T,
+}
+
+fn decodable<T: TryFrom<S3Object>>() {}
+
+fn main() {
+    decodable::<Wrapper<u8>>();
+    decodable::<Wrapper<String>>();
+}
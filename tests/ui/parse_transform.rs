@@ -0,0 +1,11 @@
+use s3_bucket::S3BucketItem;
+
+#[derive(S3BucketItem)]
+#[s3_item_prop(bucket = String::from("bucket"))]
+#[s3_item_prop(key = "items/{name|lower}")]
+#[s3_item_prop(parse)]
+pub struct Item {
+    pub name: String,
+}
+
+fn main() {}
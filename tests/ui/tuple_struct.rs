@@ -0,0 +1,7 @@
+use s3_bucket::S3BucketItem;
+
+#[derive(S3BucketItem)]
+#[s3_item_prop(bucket = String::from("bucket"))]
+pub struct Item(String);
+
+fn main() {}
@@ -0,0 +1,11 @@
+use s3_bucket::S3BucketItem;
+
+#[derive(S3BucketItem)]
+#[s3_item_prop(bucket = String::from("bucket"))]
+#[s3_item_prop(key = "items/{user_idd}/{name}")]
+pub struct Item {
+    pub user_id: String,
+    pub name: String,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use s3_bucket::S3BucketItem;
+
+#[derive(S3BucketItem)]
+#[s3_item_prop(bucket = String::from("bucket"))]
+#[s3_item_prop(key = "items/{id}")]
+#[s3_item_prop(content_typ = "text/plain")]
+pub struct Item {
+    pub id: String,
+}
+
+fn main() {}
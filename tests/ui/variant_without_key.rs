@@ -0,0 +1,11 @@
+use s3_bucket::S3BucketItem;
+
+#[derive(S3BucketItem)]
+#[s3_item_prop(bucket = String::from("bucket"))]
+pub enum Asset {
+    #[s3_item_prop(key = "images/{id}.png")]
+    Image { id: u32 },
+    Document { id: u32 },
+}
+
+fn main() {}